@@ -9,11 +9,11 @@ use crate::utils::jd_content_loader::markdown_to_projects;
 
 // Import our page components
 use crate::pages::{
-    HomePage, AboutPage, ProjectPage, AreasPage, AreaDetailPage, CategoryDetailPage
+    HomePage, AboutPage, ProjectPage, AreasPage, AreaDetailPage, CategoryDetailPage, GlossaryPage
 };
 
 // Import our regular components
-use crate::components::Header;
+use crate::components::{DefaultDescription, Header, PageDescribed};
 
 // Import models
 use crate::model::{
@@ -28,13 +28,15 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
             <head>
                 <meta charset="utf-8"/>
                 <meta name="viewport" content="width=device-width, initial-scale=1"/>
-                <meta name="description" content="Tyler Harpool's personal blog and project showcase"/>
                 <meta name="keywords" content="web development, software engineering, projects, blog"/>
                 <AutoReload options=options.clone()/>
                 <HydrationScripts options=options islands=true/>
                 <MetaTags/>
                 <link rel="stylesheet" id="leptos" href="/pkg/tylerharpool-blog.css"/>
                 <link rel="shortcut icon" type="image/ico" href="/favicon.ico"/>
+                <link rel="alternate" type="application/rss+xml" title="RSS" href="/feed.xml"/>
+                <link rel="alternate" type="application/atom+xml" title="Atom" href="/atom.xml"/>
+                <link rel="alternate" type="application/feed+json" title="JSON Feed" href="/feed.json"/>
             </head>
             <body>
                 // Our top-level App
@@ -45,7 +47,7 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
 }
 
 // Replace the create_mock_projects function with:
-fn load_projects() -> Vec<Project> {
+pub fn load_projects() -> Vec<Project> {
     // Get areas and categories using our dynamic functions
     let areas = get_all_areas();
     let categories = get_all_categories();
@@ -83,6 +85,8 @@ pub fn App() -> impl IntoView {
     let (projects_signal, _) = signal(projects);
     provide_context(projects_signal);
 
+    provide_context(PageDescribed(RwSignal::new(false)));
+
     view! {
         <Stylesheet id="leptos" href="/pkg/tylerharpool-blog.css"/>
         <Title text="Tyler Harpool - Technology & Government Blog"/>
@@ -105,8 +109,11 @@ pub fn App() -> impl IntoView {
                     <Route path=path!("/areas") view=AreasPage/>
                     <Route path=path!("/areas/:id") view=AreaDetailPage/>
                     <Route path=path!("/categories/:id") view=CategoryDetailPage/>
+                    <Route path=path!("/glossary") view=GlossaryPage/>
                 </Routes>
             </main>
         </Router>
+        // Pages with `SeoMeta` describe themselves; the rest get the site's description
+        <DefaultDescription/>
     }
 }
@@ -0,0 +1,68 @@
+// Compile (and optionally run) the ```rust blocks in blog posts.
+//
+//     cargo run --bin content-doctest -- [--run] [--category 11]... [content dir]
+//
+// Blocks marked `ignore` are skipped, `no_run` blocks are only compiled, and
+// `should_panic` / `compile_fail` blocks must fail, as with rustdoc.
+use std::process::ExitCode;
+use tylerharpool_blog::utils::doctest::{run_content_doctests, DocTestOptions, DocTestOutcome};
+
+fn main() -> ExitCode {
+    let mut options = DocTestOptions::default();
+    let mut root_dir = option_env!("CONTENT_DIR").unwrap_or("content/blog").to_string();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--run" => options.run = true,
+            "--category" => match args.next().and_then(|c| c.parse().ok()) {
+                Some(category) => options.categories.push(category),
+                None => {
+                    eprintln!("--category expects a category number, e.g. --category 11");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "--help" => {
+                println!("usage: content-doctest [--run] [--category N]... [content dir]");
+                return ExitCode::SUCCESS;
+            }
+            _ => root_dir = arg,
+        }
+    }
+
+    let results = run_content_doctests(&root_dir, &options);
+    let mut failed = 0;
+    let mut ignored = 0;
+
+    for (doctest, outcome) in &results {
+        let location = format!("{}:{}", doctest.path.display(), doctest.line);
+
+        match outcome {
+            DocTestOutcome::Passed => println!("ok      {}", location),
+            DocTestOutcome::Ignored => {
+                ignored += 1;
+                println!("ignored {}", location);
+            }
+            DocTestOutcome::Failed(output) => {
+                failed += 1;
+                println!("FAILED  {}", location);
+                for line in output.lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
+    }
+
+    println!(
+        "\n{} passed, {} failed, {} ignored",
+        results.len() - failed - ignored,
+        failed,
+        ignored
+    );
+
+    if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
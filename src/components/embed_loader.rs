@@ -0,0 +1,42 @@
+use leptos::prelude::*;
+use leptos::ev;
+use crate::utils::markdown::html_escape;
+use crate::utils::shortcodes::is_embed_url;
+
+// Swaps embed placeholders rendered by the `youtube` and `gist` shortcodes
+// for the real iframe once the reader clicks "Load content", so no
+// third-party request is made before then.
+#[island]
+pub fn EmbedLoader() -> impl IntoView {
+    let handle = window_event_listener(ev::click, |ev| {
+        use leptos::wasm_bindgen::JsCast;
+        use leptos::web_sys::Element;
+
+        let Some(target) = ev.target().and_then(|t| t.dyn_into::<Element>().ok()) else {
+            return;
+        };
+        let Ok(Some(button)) = target.closest(".embed-load") else {
+            return;
+        };
+        let Ok(Some(placeholder)) = button.closest(".embed-placeholder") else {
+            return;
+        };
+        let Some(src) = placeholder.get_attribute("data-embed-src") else {
+            return;
+        };
+
+        // Only load embeds from the providers the shortcodes use
+        if !is_embed_url(&src) {
+            return;
+        }
+
+        let title = placeholder.get_attribute("data-embed-title").unwrap_or_default();
+        placeholder.set_inner_html(&format!(
+            "<iframe src=\"{}\" title=\"{}\" loading=\"lazy\" allow=\"encrypted-media; picture-in-picture\" allowfullscreen></iframe>",
+            html_escape(&src),
+            html_escape(&title)
+        ));
+        let _ = placeholder.class_list().add_1("embed-loaded");
+    });
+    on_cleanup(move || handle.remove());
+}
@@ -14,6 +14,7 @@ pub fn Header() -> impl IntoView {
                 <nav class="main-nav">
                     <a href="/" class="nav-link">"Home"</a>
                     <a href="/areas" class="nav-link">"Areas"</a>
+                    <a href="/glossary" class="nav-link">"Glossary"</a>
                     <a href="/about" class="nav-link">"About"</a>
                 </nav>
             </div>
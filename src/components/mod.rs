@@ -1,9 +1,13 @@
+mod embed_loader;
 mod header;
 mod project_search;
 mod related_projects;
+mod seo_meta;
 mod tagged_search;
 
+pub use embed_loader::EmbedLoader;
 pub use header::Header;
 pub use self::project_search::ProjectSearch;
 pub use related_projects::RenderRelatedProjects;
+pub use seo_meta::{DefaultDescription, JsonLd, PageDescribed, SeoMeta};
 pub use tagged_search::TaggedSearch;
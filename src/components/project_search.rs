@@ -1,44 +1,98 @@
 use leptos::prelude::*;
 use leptos::logging::log;
-use crate::model::Project;
-use crate::utils::format::format_date;
+use crate::utils::client_index::{ClientIndex, IndexedPost};
+#[cfg(feature = "hydrate")]
+use crate::utils::client_index::{CurrentClientIndex, CURRENT_INDEX_URL};
+use crate::utils::facets::{facet_values, FacetSelection, FACETS};
+use crate::utils::search_query::SearchQuery;
 
 #[island]
-pub fn ProjectSearch(projects: Vec<Project>) -> impl IntoView {
-    let (projects_signal, _) = signal(projects);
-    let (search_query, set_search_query) = signal(String::new());
+pub fn ProjectSearch(
+    // Where to fetch the search index from (see `utils::client_index`)
+    index_url: String,
+    // The query and facet choices from the page URL
+    #[prop(optional)] initial_query: String,
+    #[prop(optional)] initial_facets: FacetSelection,
+    // The server's results for them, listed until the index has loaded and
+    // for visitors without JavaScript (see `ClientIndex::results`)
+    #[prop(optional)] initial_results: Vec<IndexedPost>,
+) -> impl IntoView {
+    let (index, set_index) = signal(None::<Result<ClientIndex, String>>);
+    let (search_query, set_search_query) = signal(initial_query);
+    let (facets, set_facets) = signal(initial_facets);
+    let initial_results = StoredValue::new(initial_results);
+
+    // The index is fetched once the island is running in the browser
+    #[cfg(feature = "hydrate")]
+    leptos::task::spawn_local(async move {
+        let result = fetch_index(&index_url).await;
+        if let Err(e) = &result {
+            log!("Couldn't load the search index: {}", e);
+        }
+        set_index.set(Some(result));
+    });
+    #[cfg(not(feature = "hydrate"))]
+    let _ = (index_url, set_index);
 
     // Pagination state
     let (current_page, set_current_page) = signal(1);
     let items_per_page = 5; // Adjust as needed
 
-    // Sort & filter projects
+    // The search box parsed into filters, e.g. `tag:rust after:2025-01 -draft`,
+    // with words completed and corrected from the posts' vocabulary
+    let parsed_query = Memo::new(move |_| {
+        let mut query = SearchQuery::parse(&search_query.get());
+        index.with(|index| {
+            if let Some(Ok(index)) = index {
+                query.expand(&index.vocabulary);
+            }
+        });
+        query
+    });
+
+    // Posts matching the search query, newest first
+    let query_results = Memo::new(move |_| {
+        index.with(|index| match index {
+            Some(Ok(index)) => parsed_query.with(|query| index.search(query))
+                .into_iter()
+                .cloned()
+                .collect::<Vec<IndexedPost>>(),
+            _ => Vec::new(),
+        })
+    });
+
+    // ...narrowed down by the facet panels. Until the index is here, the
+    // server's results stand in.
     let filtered_projects = move || {
-        let mut projects = projects_signal.get().clone();
-
-        // Sort by date (most recent first)
-        projects.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-
-        let q = search_query.get().to_lowercase();
+        if index.with(Option::is_none) {
+            return initial_results.get_value();
+        }
+        let results = query_results.get();
+        facets.with(|facets| results.into_iter().filter(|post| facets.matches(post)).collect::<Vec<_>>())
+    };
 
-        // Filter if search query exists
-        if !q.is_empty() {
-            projects = projects
-                .into_iter()
-                .filter(|p| {
-                    p.title.to_lowercase().contains(&q) ||
-                    p.summary.to_lowercase().contains(&q) ||
-                    p.tech_stack.iter().any(|tech| tech.to_lowercase().contains(&q)) ||
-                    p.jd_category.as_ref().map_or(false, |c|
-                        c.name.to_lowercase().contains(&q) ||
-                        c.description.to_lowercase().contains(&q)
-                    )
-                })
-                .collect::<Vec<_>>();
+    // Keep the query and facet choices in the URL, so results can be shared
+    // and survive a reload
+    #[cfg(feature = "hydrate")]
+    Effect::new(move |_| {
+        let query_string = facets.with(|facets| facets.query_string(&search_query.get()));
+        let window = window();
+        let path = window.location().pathname().unwrap_or_else(|_| "/".to_string());
+
+        if let Ok(history) = window.history() {
+            let _ = history.replace_state_with_url(
+                &wasm_bindgen::JsValue::NULL,
+                "",
+                Some(&format!("{}{}", path, query_string)),
+            );
         }
+    });
 
-        projects
-    };
+    // Before the index loads, the server's results are the answer to the same query
+    let empty_message = move || index.with(|index| match index {
+        Some(Err(_)) => "Search isn't available right now. Please try again later.",
+        _ => "No results found. Try a different search term.",
+    });
 
     // Calculate pagination metadata
     let total_items = move || filtered_projects().len();
@@ -85,163 +139,311 @@ pub fn ProjectSearch(projects: Vec<Project>) -> impl IntoView {
 
     view! {
         <div>
-            <input
-                type="text"
-                placeholder="Search projects..."
-                class="search-input"
-                on:input=move |ev| {
-                    log!("User typed: {}", event_target_value(&ev));
-                    set_search_query(event_target_value(&ev));
-                    set_current_page(1); // Reset to first page on new search
-                }
-            />
-
-            <div class="search-results">
-                <Show
-                    when=has_results
-                    fallback=move || view! {
-                        <div class="empty-search">
-                            <p>"No results found. Try a different search term."</p>
-                        </div>
+            // Without JavaScript, submitting reloads the page with `?q=`, and
+            // the server lists the results below
+            <form method="get" action="" on:submit=move |ev| ev.prevent_default()>
+                <input
+                    type="text"
+                    name="q"
+                    placeholder="Search projects... (try tag:rust or \"exact phrase\")"
+                    class="search-input"
+                    value=search_query.get_untracked()
+                    prop:value=search_query
+                    on:input=move |ev| {
+                        log!("User typed: {}", event_target_value(&ev));
+                        set_search_query(event_target_value(&ev));
+                        set_current_page(1); // Reset to first page on new search
                     }
-                >
-                    <div class="results-list">
-                        {move || {
-                            current_page_items().into_iter().map(|project| {
-                                let formatted_date = format_date(project.created_at);
-                                // Use the pre-extracted JD identifier
-                                let decimal_id = if !project.jd_identifier.is_empty() {
-                                    project.jd_identifier.clone()
-                                } else {
-                                    project.jd_category.as_ref().map_or("".to_string(), |cat| {
-                                        format!("{}", cat.id)
-                                    })
-                                };
-
-                                view! {
-                                    <div class="search-result-item">
-                                        <div class="result-header">
-                                            {(!decimal_id.is_empty()).then(|| view! {
-                                                <div class="result-decimal-container">
-                                                    <span class="result-decimal">{decimal_id}</span>
-                                                </div>
-                                            })}
+                />
+            </form>
+
+            // Active filters, each removable from the query
+            <div class="search-chips">
+                {move || parsed_query.with(|query| {
+                    query.filters().map(|(i, clause)| {
+                        let remaining = query.without(i);
+                        view! {
+                            <span class="search-chip" class:negated=clause.negated>
+                                {clause.source.clone()}
+                                <button
+                                    class="search-chip-remove"
+                                    title="Remove filter"
+                                    on:click=move |_| {
+                                        set_search_query(remaining.clone());
+                                        set_current_page(1);
+                                    }
+                                >
+                                    "×"
+                                </button>
+                            </span>
+                        }
+                    }).collect::<Vec<_>>()
+                })}
+            </div>
 
-                                            <div class="result-title-container">
-                                                <a href={format!("/projects/{}", project.slug)} class="result-title">
-                                                    {project.title}
-                                                </a>
+            // "Did you mean ...?" for words and tags no post has
+            {move || parsed_query.with(|query| query.suggestion()).map(|suggestion| {
+                let corrected = suggestion.clone();
+                view! {
+                    <p class="search-suggestion">
+                        "Did you mean "
+                        <button
+                            class="search-suggestion-link"
+                            on:click=move |_| {
+                                set_search_query(corrected.clone());
+                                set_current_page(1);
+                            }
+                        >
+                            {suggestion}
+                        </button>
+                        "?"
+                    </p>
+                }
+            })}
+
+            // Parts of the query that were ignored, and why
+            {move || parsed_query.with(|query| {
+                (!query.errors.is_empty()).then(|| view! {
+                    <ul class="search-errors">
+                        {query.errors.iter().map(|error| view! {
+                            <li>
+                                <code>{error.source.clone()}</code>
+                                ": "
+                                {error.message.clone()}
+                            </li>
+                        }).collect::<Vec<_>>()}
+                    </ul>
+                })
+            })}
+
+            <div class="search-layout">
+                // Narrow the results by area, category, tag and year, with how
+                // many results each choice would leave
+                <aside class="search-facets">
+                    {FACETS.into_iter().map(|facet| view! {
+                        <details class="facet-panel" open>
+                            <summary>{facet.title()}</summary>
+                            <ul class="facet-options">
+                                {move || {
+                                    let results = query_results.get();
+                                    let areas = index.with(|index| match index {
+                                        Some(Ok(index)) => index.areas.clone(),
+                                        _ => Vec::new(),
+                                    });
+                                    let values = facets.with(|facets| facet_values(&results, facets, facet, &areas));
+
+                                    values.into_iter().map(|option| {
+                                        let value = option.value.clone();
+                                        view! {
+                                            <li>
+                                                <label class="facet-option" class:empty={option.count == 0}>
+                                                    <input
+                                                        type="checkbox"
+                                                        prop:checked=option.selected
+                                                        on:change=move |_| {
+                                                            set_facets.update(|facets| facets.toggle(facet, &value));
+                                                            set_current_page(1);
+                                                        }
+                                                    />
+                                                    <span class="facet-label">{option.label}</span>
+                                                    <span class="facet-count">{option.count}</span>
+                                                </label>
+                                            </li>
+                                        }
+                                    }).collect::<Vec<_>>()
+                                }}
+                            </ul>
+                        </details>
+                    }).collect::<Vec<_>>()}
+                    <Show when=move || facets.with(|facets| !facets.is_empty())>
+                        <button
+                            class="facet-clear"
+                            on:click=move |_| {
+                                set_facets.set(FacetSelection::default());
+                                set_current_page(1);
+                            }
+                        >
+                            "Clear filters"
+                        </button>
+                    </Show>
+                </aside>
+
+                <div class="search-results">
+                    <Show
+                        when=has_results
+                        fallback=move || view! {
+                            <div class="empty-search">
+                                <p>{empty_message}</p>
+                            </div>
+                        }
+                    >
+                        <div class="results-list">
+                            {move || {
+                                current_page_items().into_iter().map(|project| {
+                                    // Use the pre-extracted JD identifier
+                                    let decimal_id = if !project.jd_identifier.is_empty() {
+                                        project.jd_identifier.clone()
+                                    } else {
+                                        project.jd_category.as_ref().map_or("".to_string(), |cat| {
+                                            format!("{}", cat.id)
+                                        })
+                                    };
+
+                                    view! {
+                                        <div class="search-result-item">
+                                            <div class="result-header">
+                                                {(!decimal_id.is_empty()).then(|| view! {
+                                                    <div class="result-decimal-container">
+                                                        <span class="result-decimal">{decimal_id}</span>
+                                                    </div>
+                                                })}
+
+                                                <div class="result-title-container">
+                                                    <a href={format!("/projects/{}", project.slug)} class="result-title">
+                                                        {project.title}
+                                                    </a>
+                                                </div>
                                             </div>
-                                        </div>
 
-                                        <div class="result-content">
-                                            <p class="result-summary">
-                                                {project.summary.clone()}
-                                            </p>
-                                            <div class="result-meta">
-                                                <span class="result-date">{formatted_date}</span>
-
-                                                {project.jd_category.as_ref().map(|cat| {
-                                                    view! {
-                                                        <div class="jd-info">
-                                                            <a href={format!("/areas/{}", cat.area_id)} class="jd-area-badge">
-                                                                {format!("{}-{}", cat.area_id, cat.area_id + 9)}
-                                                            </a>
-                                                            <a href={format!("/categories/{}", cat.id)} class="jd-category-badge">
-                                                                {cat.id}
-                                                            </a>
-                                                        </div>
-                                                    }
-                                                })}
+                                            <div class="result-content">
+                                                <p class="result-summary">
+                                                    {project.summary.clone()}
+                                                </p>
+                                                <div class="result-meta">
+                                                    <span class="result-date">{project.published.clone()}</span>
 
-                                                <div class="result-tags">
-                                                    {project.tech_stack.iter().map(|tech| {
-                                                        let tag_for_link = tech.clone();
-                                                        let tag_for_display = tech.clone();
+                                                    {project.jd_category.as_ref().map(|cat| {
                                                         view! {
-                                                            <a href={format!("/?tag={}", tag_for_link)} class="result-tag">
-                                                                {tag_for_display}
-                                                            </a>
+                                                            <div class="jd-info">
+                                                                <a href={format!("/areas/{}", cat.area_id)} class="jd-area-badge">
+                                                                    {format!("{}-{}", cat.area_id, cat.area_id + 9)}
+                                                                </a>
+                                                                <a href={format!("/categories/{}", cat.id)} class="jd-category-badge">
+                                                                    {cat.id}
+                                                                </a>
+                                                            </div>
                                                         }
-                                                    }).collect::<Vec<_>>()}
+                                                    })}
+
+                                                    <div class="result-tags">
+                                                        {project.tags.iter().map(|tech| {
+                                                            let tag_for_link = tech.clone();
+                                                            let tag_for_display = tech.clone();
+                                                            view! {
+                                                                <a href={format!("/?tag={}", tag_for_link)} class="result-tag">
+                                                                    {tag_for_display}
+                                                                </a>
+                                                            }
+                                                        }).collect::<Vec<_>>()}
+                                                    </div>
                                                 </div>
                                             </div>
                                         </div>
-                                    </div>
-                                }
-                            }).collect::<Vec<_>>()
-                        }}
-                    </div>
-                </Show>
-
-                // Pagination controls
-                <Show when=should_show_pagination>
-                    <div class="pagination-controls">
-                        <div class="pagination-info">
-                            {move || format!("Page {} of {}", current_page.get(), total_pages())}
+                                    }
+                                }).collect::<Vec<_>>()
+                            }}
                         </div>
-
-                        <div class="pagination-buttons">
-                            <button
-                                class="pagination-btn prev-btn"
-                                disabled={move || current_page.get() <= 1}
-                                on:click=move |_| prev_page()
-                            >
-                                "Previous"
-                            </button>
-
-                            {move || {
-                                let total = total_pages();
-                                let current = current_page.get();
-
-                                // Create page number buttons
-                                let mut page_buttons = Vec::new();
-
-                                // Show max 5 page numbers
-                                let display_count = 5.min(total);
-
-                                // Calculate the start page number
-                                let half = display_count / 2;
-                                let mut start_page = if current > half {
-                                    if current + half > total {
-                                        total - display_count + 1
+                    </Show>
+
+                    // Pagination controls
+                    <Show when=should_show_pagination>
+                        <div class="pagination-controls">
+                            <div class="pagination-info">
+                                {move || format!("Page {} of {}", current_page.get(), total_pages())}
+                            </div>
+
+                            <div class="pagination-buttons">
+                                <button
+                                    class="pagination-btn prev-btn"
+                                    disabled={move || current_page.get() <= 1}
+                                    on:click=move |_| prev_page()
+                                >
+                                    "Previous"
+                                </button>
+
+                                {move || {
+                                    let total = total_pages();
+                                    let current = current_page.get();
+
+                                    // Create page number buttons
+                                    let mut page_buttons = Vec::new();
+
+                                    // Show max 5 page numbers
+                                    let display_count = 5.min(total);
+
+                                    // Calculate the start page number
+                                    let half = display_count / 2;
+                                    let mut start_page = if current > half {
+                                        if current + half > total {
+                                            total - display_count + 1
+                                        } else {
+                                            current - half
+                                        }
                                     } else {
-                                        current - half
+                                        1
+                                    };
+
+                                    start_page = start_page.max(1);
+
+                                    for page in start_page..=(start_page + display_count - 1).min(total) {
+                                        let is_active = page == current;
+                                        let page_for_closure = page;
+
+                                        page_buttons.push(view! {
+                                            <button
+                                                class={if is_active { "pagination-btn page-btn active" } else { "pagination-btn page-btn" }}
+                                                on:click=move |_| go_to_page(page_for_closure)
+                                            >
+                                                {page}
+                                            </button>
+                                        });
                                     }
-                                } else {
-                                    1
-                                };
-
-                                start_page = start_page.max(1);
-
-                                for page in start_page..=(start_page + display_count - 1).min(total) {
-                                    let is_active = page == current;
-                                    let page_for_closure = page;
-
-                                    page_buttons.push(view! {
-                                        <button
-                                            class={if is_active { "pagination-btn page-btn active" } else { "pagination-btn page-btn" }}
-                                            on:click=move |_| go_to_page(page_for_closure)
-                                        >
-                                            {page}
-                                        </button>
-                                    });
-                                }
 
-                                page_buttons
-                            }}
-
-                            <button
-                                class="pagination-btn next-btn"
-                                disabled={move || current_page.get() >= total_pages()}
-                                on:click=move |_| next_page()
-                            >
-                                "Next"
-                            </button>
+                                    page_buttons
+                                }}
+
+                                <button
+                                    class="pagination-btn next-btn"
+                                    disabled={move || current_page.get() >= total_pages()}
+                                    on:click=move |_| next_page()
+                                >
+                                    "Next"
+                                </button>
+                            </div>
                         </div>
-                    </div>
-                </Show>
+                    </Show>
+                </div>
             </div>
         </div>
     }
 }
+
+#[cfg(feature = "hydrate")]
+async fn fetch_index(url: &str) -> Result<ClientIndex, String> {
+    let response = fetch(url).await?;
+
+    // The posts changed since the page was rendered, so the index it names
+    // is gone; ask which one is current and fetch that instead
+    let response = if response.status() == 404 {
+        let current: CurrentClientIndex = read_json(fetch(CURRENT_INDEX_URL).await?).await?;
+        fetch(&current.url).await?
+    } else {
+        response
+    };
+
+    read_json(response).await
+}
+
+#[cfg(feature = "hydrate")]
+async fn fetch(url: &str) -> Result<gloo_net::http::Response, String> {
+    gloo_net::http::Request::get(url).send().await.map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "hydrate")]
+async fn read_json<T: serde::de::DeserializeOwned>(response: gloo_net::http::Response) -> Result<T, String> {
+    if !response.ok() {
+        return Err(format!("{} returned {}", response.url(), response.status()));
+    }
+
+    response.json().await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,76 @@
+use leptos::prelude::*;
+use leptos_meta::{Link, Meta};
+use serde_json::Value;
+use crate::utils::seo::{absolute_url, json_ld_script};
+use crate::utils::site_config::get_site_config;
+
+// Set once a page renders `SeoMeta`, so `DefaultDescription` knows whether
+// the page already has a description of its own
+#[derive(Clone, Copy)]
+pub struct PageDescribed(pub RwSignal<bool>);
+
+// Description, canonical URL, Open Graph and Twitter card tags for a page.
+// `path` is the page's root-relative path; `image` may be relative too.
+#[component]
+pub fn SeoMeta(
+    title: String,
+    description: String,
+    path: String,
+    #[prop(optional_no_strip)] image: Option<String>,
+    // "article" for posts, "website" otherwise
+    #[prop(default = "website")] og_type: &'static str,
+    // Publication time and tags, for articles
+    #[prop(optional)] published_time: Option<String>,
+    #[prop(optional)] tags: Vec<String>,
+) -> impl IntoView {
+    let site = &get_site_config().site;
+    let url = absolute_url(&path);
+    let image = image.map(|image| absolute_url(&image));
+    let card = if image.is_some() { "summary_large_image" } else { "summary" };
+
+    if let Some(PageDescribed(described)) = use_context::<PageDescribed>() {
+        described.set(true);
+    }
+
+    view! {
+        <Meta name="description" content=description.clone()/>
+        <Link rel="canonical" href=url.clone()/>
+
+        <Meta property="og:type" content=og_type/>
+        <Meta property="og:site_name" content=site.title.clone()/>
+        <Meta property="og:title" content=title.clone()/>
+        <Meta property="og:description" content=description.clone()/>
+        <Meta property="og:url" content=url/>
+        {image.clone().map(|image| view! { <Meta property="og:image" content=image/> })}
+        {published_time.map(|time| view! { <Meta property="article:published_time" content=time/> })}
+        {tags.into_iter().map(|tag| view! { <Meta property="article:tag" content=tag/> }).collect::<Vec<_>>()}
+
+        <Meta name="twitter:card" content=card/>
+        <Meta name="twitter:title" content=title/>
+        <Meta name="twitter:description" content=description/>
+        {image.map(|image| view! { <Meta name="twitter:image" content=image/> })}
+    }
+}
+
+// The site's own description, for pages that don't render `SeoMeta` (such
+// as "Page not found"). Must come after the page in the view, so it can see
+// whether the page set a description.
+#[component]
+pub fn DefaultDescription() -> impl IntoView {
+    let described = use_context::<PageDescribed>();
+
+    move || {
+        let has_description = described.is_some_and(|PageDescribed(described)| described.get());
+        (!has_description).then(|| view! {
+            <Meta name="description" content=get_site_config().site.description.clone()/>
+        })
+    }
+}
+
+// Structured data (schema.org JSON-LD) for search engines
+#[component]
+pub fn JsonLd(data: Vec<Value>) -> impl IntoView {
+    view! {
+        <script type="application/ld+json" inner_html=json_ld_script(&data)></script>
+    }
+}
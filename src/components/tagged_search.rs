@@ -1,50 +1,26 @@
 use leptos::prelude::*;
 use crate::model::Project;
 use crate::components::ProjectSearch;
+use crate::utils::client_index::client_index;
+use crate::utils::facets::FacetSelection;
 
 #[component]
 pub fn TaggedSearch(
     projects: Vec<Project>,
-    #[prop(default = String::new())] active_tag: String
+    // The search query and facet choices from the URL, e.g. `/?q=leptos&tag=rust`
+    #[prop(default = String::new())] query: String,
+    #[prop(default = FacetSelection::default())] facets: FacetSelection,
 ) -> impl IntoView {
-    // Create a signal to store active_tag so we can use it multiple times
-    let (tag_signal, _) = signal(active_tag);
-
-    // Pre-filter projects by tag
-    let filtered_projects = move || {
-        let tag = tag_signal.get();
-
-        if tag.is_empty() {
-            // No tag filter, return all projects
-            projects.clone()
-        } else {
-            // Filter projects by tag
-            let tag_lower = tag.to_lowercase();
-            projects.iter()
-                .filter(|p| p.tech_stack.iter().any(|t| t.to_lowercase() == tag_lower))
-                .cloned()
-                .collect()
-        }
-    };
+    // The island fetches the posts itself, from the prebuilt search index.
+    // The first results are rendered here, so they're there without JavaScript.
+    let asset = client_index(&projects);
+    let results = asset.index.results(&query, &facets);
 
     view! {
         <div>
-            // Tag filter banner
-            {move || {
-                let tag = tag_signal.get();
-                (!tag.is_empty()).then(|| view! {
-                    <div class="tag-filter-banner">
-                        <p>
-                            "Filtering by tag: "
-                            <span class="active-tag">{tag}</span>
-                            <a href="/" class="clear-filter" title="Clear filter">X</a>
-                        </p>
-                    </div>
-                })
-            }}
-
-            // Use the existing working component with filtered projects
-            <ProjectSearch projects={filtered_projects()} />
+            // Filters by tag (including child tags of a parent tag), area,
+            // category and year are the island's facet panels
+            <ProjectSearch index_url=asset.url() initial_query=query initial_facets=facets initial_results=results />
         </div>
     }
 }
@@ -3,11 +3,19 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
+    use std::collections::HashMap;
+    use axum::extract::{Path, Query};
+    use axum::routing::get;
     use axum::Router;
     use leptos::logging::log;
     use leptos::prelude::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use tylerharpool_blog::app::*;
+    use tylerharpool_blog::utils::feeds::{FeedFormat, FeedScope};
+    use tylerharpool_blog::utils::search::{search, search_index};
+    use tylerharpool_blog::utils::search_query::SearchQuery;
+    use tylerharpool_blog::utils::site_config::get_site_config;
+    use tylerharpool_blog::utils::sitemap::{robots_txt, sitemap_part_xml, sitemap_xml};
 
     let conf = get_configuration(None).unwrap();
     let addr = conf.leptos_options.site_addr;
@@ -16,6 +24,55 @@ async fn main() {
     let routes = generate_route_list(App);
 
     let app = Router::new()
+        // Syndication feeds for the whole site and each area, category and tag
+        .route("/feed.xml", get(|| async { feed_response(FeedScope::Site, FeedFormat::Rss) }))
+        .route("/atom.xml", get(|| async { feed_response(FeedScope::Site, FeedFormat::Atom) }))
+        .route("/feed.json", get(|| async { feed_response(FeedScope::Site, FeedFormat::Json) }))
+        .route("/areas/:id/feed.xml", get(|Path(id): Path<u8>| async move {
+            feed_response(FeedScope::Area(id), FeedFormat::Rss)
+        }))
+        .route("/categories/:id/feed.xml", get(|Path(id): Path<u8>| async move {
+            feed_response(FeedScope::Category(id), FeedFormat::Rss)
+        }))
+        .route("/tags/:tag/feed.xml", get(|Path(tag): Path<String>| async move {
+            feed_response(FeedScope::Tag(tag), FeedFormat::Rss)
+        }))
+        // Social preview images, e.g. /og/my-post.png
+        .route("/og/:file", get(|Path(file): Path<String>| async move { og_image_response(&file) }))
+        // Sitemaps and crawler rules
+        .route("/sitemap.xml", get(|| async {
+            document_response(Some(sitemap_xml(&load_projects())), "application/xml; charset=utf-8")
+        }))
+        .route("/sitemap/:file", get(|Path(file): Path<String>| async move {
+            let part = file.strip_suffix(".xml").and_then(|n| n.parse().ok());
+            let xml = part.and_then(|part| sitemap_part_xml(&load_projects(), part));
+            document_response(xml, "application/xml; charset=utf-8")
+        }))
+        .route("/robots.txt", get(|| async {
+            document_response(Some(robots_txt()), "text/plain; charset=utf-8")
+        }))
+        // The search island's index, e.g. /search/index.3f2a9c0e5b7d1f4a.json
+        .route("/search/:file", get(|Path(file): Path<String>| async move { client_index_response(&file) }))
+        // Full-text search, e.g. /api/search?q=rust+macros&limit=10
+        .route("/api/search", get(|Query(params): Query<HashMap<String, String>>| async move {
+            let query = params.get("q").cloned().unwrap_or_default();
+            let max = get_site_config().search.limit;
+            let limit = params.get("limit")
+                .and_then(|limit| limit.parse::<usize>().ok())
+                .map_or(max, |limit| limit.min(max));
+            let projects = load_projects();
+            let index = search_index(&projects);
+            let mut parsed = SearchQuery::parse(&query);
+            parsed.expand(index.vocabulary());
+            let hits = search(&projects, &index, &parsed, limit);
+            let body = serde_json::json!({
+                "query": query,
+                "hits": hits,
+                "errors": parsed.errors,
+                "suggestion": parsed.suggestion(),
+            });
+            document_response(Some(body.to_string()), "application/json")
+        }))
         .leptos_routes(&leptos_options, routes, {
             let leptos_options = leptos_options.clone();
             move || shell(leptos_options.clone())
@@ -32,6 +89,87 @@ async fn main() {
         .unwrap();
 }
 
+// Render a feed from the current content, or 404 for unknown areas, categories and tags
+#[cfg(feature = "ssr")]
+fn feed_response(
+    scope: tylerharpool_blog::utils::feeds::FeedScope,
+    format: tylerharpool_blog::utils::feeds::FeedFormat,
+) -> axum::response::Response {
+    use tylerharpool_blog::app::load_projects;
+    use tylerharpool_blog::utils::feeds::build_feed;
+
+    let projects = load_projects();
+    let feed = build_feed(&projects, &scope, format).map(|feed| format.render(&feed));
+
+    document_response(feed, format.content_type())
+}
+
+// The generated Open Graph image for a post
+#[cfg(feature = "ssr")]
+fn og_image_response(file: &str) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+    use leptos::logging::log;
+    use tylerharpool_blog::app::load_projects;
+    use tylerharpool_blog::utils::og_image::og_image_png;
+
+    let projects = load_projects();
+    let Some(project) = file.strip_suffix(".png").and_then(|slug| projects.iter().find(|p| p.slug == slug)) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match og_image_png(project) {
+        Ok(png) => (
+            [(header::CONTENT_TYPE, "image/png"), (header::CACHE_CONTROL, "public, max-age=86400")],
+            png,
+        ).into_response(),
+        Err(e) => {
+            log!("Couldn't render social preview for {}: {}", project.slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// The fingerprinted search index. Its name changes with its contents,
+// so it can be cached for good. `current.json` names the current one, for
+// pages rendered before the posts changed.
+#[cfg(feature = "ssr")]
+fn client_index_response(file: &str) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+    use tylerharpool_blog::app::load_projects;
+    use tylerharpool_blog::utils::client_index::{client_index, CurrentClientIndex, CURRENT_INDEX_URL};
+
+    let asset = client_index(&load_projects());
+    if CURRENT_INDEX_URL.strip_prefix("/search/") == Some(file) {
+        let current = CurrentClientIndex { url: asset.url() };
+        return (
+            [(header::CONTENT_TYPE, "application/json"), (header::CACHE_CONTROL, "no-cache")],
+            serde_json::to_string(&current).unwrap_or_default(),
+        ).into_response();
+    }
+    if file != asset.file_name {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    (
+        [(header::CONTENT_TYPE, "application/json"), (header::CACHE_CONTROL, "public, max-age=31536000, immutable")],
+        asset.json.clone(),
+    ).into_response()
+}
+
+// A generated document with its content type, or 404 when there's nothing to serve
+#[cfg(feature = "ssr")]
+fn document_response(body: Option<String>, content_type: &'static str) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+
+    match body {
+        Some(body) => ([(header::CONTENT_TYPE, content_type)], body).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
 #[cfg(not(feature = "ssr"))]
 pub fn main() {
     // no client-side main function
@@ -21,7 +21,46 @@ pub struct Project {
     // New fields
     pub original_path: String,  // Store the original file path
     pub jd_identifier: String,  // Store the Johnny Decimal ID (e.g., "21.01")
-    pub related_articles: Vec<String>
+    pub related_articles: Vec<String>,
+    pub allow_html: bool,       // Skip HTML sanitising for this post (front matter `allow_html: true`)
+
+    // Pre-rendered at load time on the server; never sent to islands
+    #[serde(skip)]
+    pub rendered: Option<RenderedContent>,
+}
+
+// Output of rendering a post's markdown once
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RenderedContent {
+    pub html: String,
+    pub toc: Vec<TocEntry>,
+    pub plain_text: String,
+    pub bibliography: Vec<BibliographyEntry>,
+}
+
+// A heading in a post's table of contents
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TocEntry {
+    pub level: u8,
+    pub id: String,
+    pub text: String,
+}
+
+// A term from the site glossary (content/glossary.md or content/glossary/*.md)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GlossaryEntry {
+    pub term: String,
+    pub slug: String,
+    pub aliases: Vec<String>,
+    pub definition: String,  // Markdown
+}
+
+// A reference cited in a post, listed in its bibliography
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BibliographyEntry {
+    pub id: String,     // Anchor that citations link to, e.g. "ref-smith2020"
+    pub label: String,  // "1" for numbered styles, empty for author-date
+    pub html: String,   // The formatted reference
 }
 
 // Johnny Decimal System structures
@@ -1,11 +1,17 @@
 use leptos::prelude::*;
 use leptos_meta::Title;
+use crate::components::SeoMeta;
 
 #[component]
 pub fn AboutPage() -> impl IntoView {
     view! {
         <div class="about-page container">
             <Title text="About - Tyler Harpool"/>
+            <SeoMeta
+                title="About Tyler Harpool".to_string()
+                description="Tyler Harpool's background in software engineering and government technology".to_string()
+                path="/about".to_string()
+            />
 
             <div class="about-header">
                 <h1>"About Tyler Harpool"</h1>
@@ -1,7 +1,9 @@
 use leptos::prelude::*;
-use leptos_meta::Title;
+use leptos_meta::{Link, Title};
 use leptos_router::hooks::use_params_map;
+use crate::components::{JsonLd, SeoMeta};
 use crate::model::{JDArea, JDCategory};
+use crate::utils::seo::{breadcrumb_json_ld, breadcrumb_trail};
 
 #[component]
 pub fn AreaDetailPage() -> impl IntoView {
@@ -47,9 +49,22 @@ pub fn AreaDetailPage() -> impl IntoView {
         >
             {move || {
                 let area = current_area().unwrap();
+                let trail = breadcrumb_trail(Some(&area), None, None);
                 view! {
                     <div class="area-detail container">
                         <Title text={format!("{} - Tyler Harpool", area.name.clone())}/>
+                        <Link
+                            rel="alternate"
+                            type_="application/rss+xml"
+                            title={format!("{} (RSS)", area.name)}
+                            href={format!("/areas/{}/feed.xml", area.id)}
+                        />
+                        <SeoMeta
+                            title=area.name.clone()
+                            description=area.description.clone()
+                            path=format!("/areas/{}", area.id)
+                        />
+                        <JsonLd data=vec![breadcrumb_json_ld(&trail)]/>
 
                         <header class="area-header">
                             <div class="breadcrumbs">
@@ -1,6 +1,7 @@
 use crate::{JDArea, JDCategory};  // Ensure correct imports for JDArea and JDCategory
 use leptos::prelude::*;
 use leptos_meta::Title;  // Import Title for setting the document title
+use crate::components::SeoMeta;
 use std::path::Path;  // Import Path for file handling
 use std::fs;
 use leptos::logging::log;
@@ -20,6 +21,11 @@ pub fn AreasPage() -> impl IntoView {
     view! {
         <div class="container">
             <Title text="Browse by Area - Tyler Harpool"/>
+            <SeoMeta
+                title="Browse by Area".to_string()
+                description="Articles organised by Johnny Decimal area and category".to_string()
+                path="/areas".to_string()
+            />
             <h1>"Browse by Area"</h1>
             <p class="intro-text">
                 "This blog is organized using the Johnny Decimal system, which groups content into meaningful areas and categories."
@@ -1,8 +1,10 @@
 use leptos::prelude::*;
-use leptos_meta::Title;
+use leptos_meta::{Link, Title};
 use leptos_router::hooks::use_params_map;
 use crate::model::{JDArea, JDCategory, Project};
+use crate::components::{JsonLd, SeoMeta};
 use crate::utils::format::format_date;
+use crate::utils::seo::{breadcrumb_json_ld, breadcrumb_trail};
 
 #[component]
 pub fn CategoryDetailPage() -> impl IntoView {
@@ -63,6 +65,7 @@ pub fn CategoryDetailPage() -> impl IntoView {
         >
             {move || {
                 let category = current_category().unwrap();
+                let trail = breadcrumb_trail(parent_area().as_ref(), Some(&category), None);
                 let area = parent_area().unwrap_or_else(|| JDArea {
                     id: 0,
                     name: "Unknown Area".into(),
@@ -72,6 +75,18 @@ pub fn CategoryDetailPage() -> impl IntoView {
                 view! {
                     <div class="category-detail container">
                         <Title text={format!("{} - Tyler Harpool", category.name.clone())}/>
+                        <Link
+                            rel="alternate"
+                            type_="application/rss+xml"
+                            title={format!("{} (RSS)", category.name)}
+                            href={format!("/categories/{}/feed.xml", category.id)}
+                        />
+                        <SeoMeta
+                            title=category.name.clone()
+                            description=category.description.clone()
+                            path=format!("/categories/{}", category.id)
+                        />
+                        <JsonLd data=vec![breadcrumb_json_ld(&trail)]/>
 
                         <header class="category-header">
                             <div class="breadcrumbs">
@@ -0,0 +1,69 @@
+use leptos::prelude::*;
+use leptos_meta::Title;
+use crate::components::SeoMeta;
+use crate::utils::glossary::get_glossary;
+use crate::utils::markdown::markdown_to_html;
+
+#[component]
+pub fn GlossaryPage() -> impl IntoView {
+    let entries = get_glossary().entries.clone();
+
+    // First letters, for the jump links at the top
+    let mut letters: Vec<char> = entries.iter()
+        .filter_map(|e| e.term.chars().next())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    letters.dedup();
+
+    view! {
+        <div class="container glossary-page">
+            <Title text="Glossary - Tyler Harpool"/>
+            <SeoMeta
+                title="Glossary".to_string()
+                description="Definitions of the terms and abbreviations used across the blog".to_string()
+                path="/glossary".to_string()
+            />
+            <h1>"Glossary"</h1>
+            <p class="intro-text">
+                "Terms and abbreviations used across the blog. Linked terms in posts point here."
+            </p>
+
+            {if entries.is_empty() {
+                view! { <p class="no-results">"No glossary terms yet."</p> }.into_any()
+            } else {
+                view! {
+                    <nav class="glossary-letters">
+                        {letters.into_iter().map(|letter| view! {
+                            <a href=format!("#letter-{}", letter)>{letter.to_string()}</a>
+                        }).collect_view()}
+                    </nav>
+
+                    <dl class="glossary">
+                        {entries.iter().enumerate().map(|(i, entry)| {
+                            let letter = entry.term.chars().next().map(|c| c.to_ascii_uppercase());
+                            let previous = i.checked_sub(1)
+                                .and_then(|p| entries[p].term.chars().next())
+                                .map(|c| c.to_ascii_uppercase());
+                            let letter_anchor = (letter != previous)
+                                .then(|| letter.map(|l| format!("letter-{}", l)))
+                                .flatten();
+
+                            let aliases = (!entry.aliases.is_empty()).then(|| view! {
+                                <span class="glossary-aliases">{format!("Also: {}", entry.aliases.join(", "))}</span>
+                            });
+
+                            view! {
+                                <dt id=entry.slug.clone()>
+                                    <span id=letter_anchor></span>
+                                    <a href=format!("#{}", entry.slug) class="glossary-anchor">{entry.term.clone()}</a>
+                                    {aliases}
+                                </dt>
+                                <dd inner_html=markdown_to_html(&entry.definition)></dd>
+                            }
+                        }).collect_view()}
+                    </dl>
+                }.into_any()
+            }}
+        </div>
+    }
+}
@@ -1,7 +1,10 @@
 use leptos::prelude::*;
 use crate::model::Project;
-use crate::components::TaggedSearch;
-use leptos_meta::Title;
+use crate::components::{SeoMeta, TaggedSearch};
+use leptos_meta::{Link, Title};
+use crate::utils::facets::FacetSelection;
+use crate::utils::feeds::FeedScope;
+use crate::utils::site_config::get_site_config;
 use leptos_router::hooks::use_query_map;
 
 #[component]
@@ -11,8 +14,10 @@ pub fn HomePage(projects: Vec<Project>) -> impl IntoView {
     let items_per_page = 6;
 
     let query = use_query_map();
+    // A single tag filter gets the tag's own metadata and feed; several
+    // tags (`?tag=rust,wasm`) are just a search
     let tag_filter = move || {
-        query.with(|q| q.get("tag").map(|s| s.clone()).unwrap_or_default())
+        query.with(|q| q.get("tag").map(|s| s.clone()).filter(|tag| !tag.contains(',')).unwrap_or_default())
     };
     // Get most recent posts
     let recent_posts = move || {
@@ -65,6 +70,24 @@ pub fn HomePage(projects: Vec<Project>) -> impl IntoView {
     view! {
         <div class="container">
             <Title text="Tyler Harpool - Technology & Government Blog"/>
+            // Metadata and feed for the tag being filtered on
+            {move || {
+                let tag = tag_filter();
+                let site = &get_site_config().site;
+                let path = if tag.is_empty() { "/".to_string() } else { FeedScope::Tag(tag.clone()).page_path() };
+
+                view! {
+                    <SeoMeta title=site.title.clone() description=site.description.clone() path=path/>
+                    {(!tag.is_empty()).then(|| view! {
+                        <Link
+                            rel="alternate"
+                            type_="application/rss+xml"
+                            title={format!("#{} (RSS)", tag)}
+                            href={FeedScope::Tag(tag.clone()).feed_path("feed.xml")}
+                        />
+                    })}
+                }
+            }}
             <h1>"Tyler Harpool's Blog"</h1>
             <p class="intro-text">
                 "Welcome to my blog where I share my thoughts on software architecture,
@@ -74,7 +97,8 @@ pub fn HomePage(projects: Vec<Project>) -> impl IntoView {
             <div class="search-box">
             <TaggedSearch
                 projects={projects.clone()}
-                active_tag={tag_filter()}
+                query={query.with(|q| q.get("q").map(|s| s.to_string()).unwrap_or_default())}
+                facets={query.with(|q| FacetSelection::from_params(|key| q.get(key).map(|s| s.to_string())))}
             />
             </div>
 
@@ -2,6 +2,7 @@ mod about;
 mod areas;
 mod area_detail;
 mod category_detail;
+mod glossary;
 mod home;
 mod project;
 
@@ -9,5 +10,6 @@ pub use about::AboutPage;
 pub use areas::AreasPage;
 pub use area_detail::AreaDetailPage;
 pub use category_detail::CategoryDetailPage;
+pub use glossary::GlossaryPage;
 pub use home::HomePage;
 pub use project::ProjectPage;
@@ -1,9 +1,12 @@
 use leptos::prelude::*;
 use leptos_meta::Title;
 use leptos_router::hooks::use_params_map;
+use chrono::{DateTime, Utc};
 use crate::model::{Project, JDArea};
-use crate::utils::{format::format_date, markdown::markdown_to_html};
-use crate::components::RenderRelatedProjects;
+use leptos::logging::log;
+use crate::utils::{format::format_date, markdown::markdown_to_html_with_options, transforms::RenderContext};
+use crate::components::{EmbedLoader, JsonLd, RenderRelatedProjects, SeoMeta};
+use crate::utils::seo::{blog_posting_json_ld, breadcrumb_json_ld, breadcrumb_trail};
 
 #[component]
 pub fn ProjectPage() -> impl IntoView {
@@ -33,9 +36,45 @@ pub fn ProjectPage() -> impl IntoView {
         .unwrap_or_else(|| "Unknown".to_string())
     };
 
-    // Convert current project's content to HTML
+    // Use the HTML rendered when content was loaded, rendering on demand if it's missing
     let content_html = move || {
-        current_project().map(|proj| markdown_to_html(&proj.content)).unwrap_or_default()
+        current_project()
+            .map(|proj| {
+                if let Some(rendered) = proj.rendered {
+                    return rendered.html;
+                }
+
+                let projects = projects_signal.get();
+                let mut ctx = RenderContext::new(&projects).with_source_path(&proj.original_path);
+                let html = markdown_to_html_with_options(&proj.content, proj.allow_html, &mut ctx);
+
+                for problem in &ctx.diagnostics {
+                    log!("{}: {}", proj.original_path, problem);
+                }
+
+                html
+            })
+            .unwrap_or_default()
+    };
+
+    // Section headings for the table of contents
+    let toc_entries = move || {
+        current_project()
+            .and_then(|proj| proj.rendered)
+            .map(|rendered| {
+                rendered.toc.into_iter()
+                    .filter(|entry| entry.level == 2 || entry.level == 3)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+
+    // Works cited in the post, listed after the content
+    let bibliography = move || {
+        current_project()
+            .and_then(|proj| proj.rendered)
+            .map(|rendered| rendered.bibliography)
+            .unwrap_or_default()
     };
 
     // Show the project or a fallback if none found
@@ -52,9 +91,34 @@ pub fn ProjectPage() -> impl IntoView {
                 let project = current_project().unwrap();
                 let title = project.title.clone();
 
+                // Search and social metadata, with the same breadcrumb trail as the header
+                let areas = use_context::<ReadSignal<Vec<JDArea>>>()
+                    .map(|areas| areas.get())
+                    .unwrap_or_default();
+                let area = project.jd_category.as_ref()
+                    .and_then(|cat| areas.iter().find(|a| a.id == cat.area_id));
+                let trail = breadcrumb_trail(area, project.jd_category.as_ref(), Some(&project));
+                // Posts without a thumbnail share a generated card
+                let share_image = project.thumbnail.clone()
+                    .unwrap_or_else(|| format!("/og/{}.png", project.slug));
+                let structured_data = vec![
+                    blog_posting_json_ld(&project, Some(&share_image)),
+                    breadcrumb_json_ld(&trail),
+                ];
+
                 view! {
                     <div class="project-detail container">
                         <Title text={format!("{} - Tyler Harpool", title)}/>
+                        <SeoMeta
+                            title=title.clone()
+                            description=project.summary.clone()
+                            path=format!("/projects/{}", project.slug)
+                            image=Some(share_image)
+                            og_type="article"
+                            published_time=DateTime::<Utc>::from(project.created_at).to_rfc3339()
+                            tags=project.tech_stack.clone()
+                        />
+                        <JsonLd data=structured_data/>
 
                         <header class="project-header">
                             {project.jd_category.as_ref().map(|cat| {
@@ -140,10 +204,46 @@ pub fn ProjectPage() -> impl IntoView {
                             <p>{project.summary.clone()}</p>
                         </div>
 
+                        {move || {
+                            let entries = toc_entries();
+                            (entries.len() > 1).then(|| view! {
+                                <nav class="project-toc" aria-label="Table of contents">
+                                    <h2>"Contents"</h2>
+                                    <ul>
+                                        {entries.into_iter().map(|entry| view! {
+                                            <li class={format!("toc-level-{}", entry.level)}>
+                                                <a href={format!("#{}", entry.id)}>{entry.text}</a>
+                                            </li>
+                                        }).collect::<Vec<_>>()}
+                                    </ul>
+                                </nav>
+                            })
+                        }}
+
                         <div class="project-content">
                             <div inner_html={content_html()}></div>
+                            <EmbedLoader/>
                         </div>
 
+                        {move || {
+                            let entries = bibliography();
+                            (!entries.is_empty()).then(|| view! {
+                                <section class="bibliography" aria-labelledby="references">
+                                    <h2 id="references">"References"</h2>
+                                    <ol class="bibliography-list">
+                                        {entries.into_iter().map(|entry| view! {
+                                            <li id=entry.id>
+                                                {(!entry.label.is_empty()).then(|| view! {
+                                                    <span class="bibliography-label">{format!("[{}]", entry.label)}</span>
+                                                })}
+                                                <span inner_html=entry.html></span>
+                                            </li>
+                                        }).collect::<Vec<_>>()}
+                                    </ol>
+                                </section>
+                            })
+                        }}
+
                         <footer class="project-footer">
                             {project.jd_category.as_ref().map(|cat| {
 
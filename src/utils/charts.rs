@@ -0,0 +1,420 @@
+use std::path::{Component, Path, PathBuf};
+use crate::utils::markdown::{code_fence, html_escape};
+use crate::utils::shortcodes::{tokenize, unquote};
+
+// Parsed CSV: a header row and the data rows, all as text
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartType {
+    Line,
+    Bar,
+}
+
+impl ChartType {
+    pub fn parse(name: &str) -> Result<ChartType, String> {
+        match name.to_lowercase().as_str() {
+            "line" => Ok(ChartType::Line),
+            "bar" => Ok(ChartType::Bar),
+            other => Err(format!("unknown chart type `{}` (expected line or bar)", other)),
+        }
+    }
+}
+
+// Chart geometry, in SVG user units
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 360.0;
+const MARGIN_LEFT: f64 = 56.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 32.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+const Y_TICKS: usize = 5;
+// Label every nth x value so labels don't overlap
+const MAX_X_LABELS: usize = 12;
+
+// Parse CSV text. The first row is the header; quoted fields may contain
+// commas, doubled quotes and line breaks.
+pub fn parse_csv(text: &str) -> Result<DataTable, String> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field).trim().to_string()),
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field).trim().to_string());
+                if row.iter().any(|f| !f.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            '\r' if !in_quotes => {}
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err("unterminated quoted field".to_string());
+    }
+
+    row.push(field.trim().to_string());
+    if row.iter().any(|f| !f.is_empty()) {
+        rows.push(row);
+    }
+
+    let mut rows = rows.into_iter();
+    let headers = rows.next().ok_or("no rows")?;
+
+    let rows: Vec<Vec<String>> = rows.collect();
+    if let Some((i, row)) = rows.iter().enumerate().find(|(_, r)| r.len() != headers.len()) {
+        return Err(format!(
+            "row {} has {} fields but the header has {}",
+            i + 2, row.len(), headers.len()
+        ));
+    }
+
+    Ok(DataTable { headers, rows })
+}
+
+// Rust also parses "NaN" and "inf", which can't be plotted
+fn parse_number(value: &str) -> Option<f64> {
+    value.trim().trim_end_matches('%').replace(',', "").parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+// An accessible HTML table for the data
+pub fn render_table(table: &DataTable, caption: Option<&str>) -> String {
+    let numeric: Vec<bool> = (0..table.headers.len())
+        .map(|col| !table.rows.is_empty() && table.rows.iter().all(|r| parse_number(&r[col]).is_some()))
+        .collect();
+
+    let mut html = String::from("<div class=\"data-table\"><table>");
+
+    if let Some(caption) = caption {
+        html.push_str(&format!("<caption>{}</caption>", html_escape(caption)));
+    }
+
+    html.push_str("<thead><tr>");
+    for (col, header) in table.headers.iter().enumerate() {
+        let class = if numeric[col] { " class=\"numeric\"" } else { "" };
+        html.push_str(&format!("<th scope=\"col\"{}>{}</th>", class, html_escape(header)));
+    }
+    html.push_str("</tr></thead><tbody>");
+
+    for row in &table.rows {
+        html.push_str("<tr>");
+        for (col, value) in row.iter().enumerate() {
+            if col == 0 {
+                html.push_str(&format!("<th scope=\"row\">{}</th>", html_escape(value)));
+            } else {
+                let class = if numeric[col] { " class=\"numeric\"" } else { "" };
+                html.push_str(&format!("<td{}>{}</td>", class, html_escape(value)));
+            }
+        }
+        html.push_str("</tr>");
+    }
+
+    html.push_str("</tbody></table></div>");
+    html
+}
+
+// Options from a ```chart block's info string
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartOptions {
+    pub chart_type: ChartType,
+    pub title: Option<String>,
+    // Column holding the x values (the first column by default)
+    pub x: Option<String>,
+    // Columns to plot (every other numeric column by default)
+    pub y: Vec<String>,
+}
+
+// A static SVG chart of the data, with a title and description for screen
+// readers and the underlying table in a disclosure below it. `id` must be
+// unique on the page.
+pub fn render_chart(table: &DataTable, options: &ChartOptions, id: &str) -> Result<String, String> {
+    let column = |name: &str| table.headers.iter().position(|h| h.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("no column named `{}`", name));
+
+    let x_col = match &options.x {
+        Some(name) => column(name)?,
+        None => 0,
+    };
+
+    let y_cols: Vec<usize> = if options.y.is_empty() {
+        (0..table.headers.len())
+            .filter(|&col| col != x_col && table.rows.iter().all(|r| parse_number(&r[col]).is_some()))
+            .collect()
+    } else {
+        options.y.iter().map(|name| column(name)).collect::<Result<_, _>>()?
+    };
+
+    if y_cols.is_empty() {
+        return Err("no numeric columns to plot".to_string());
+    }
+    if table.rows.is_empty() {
+        return Err("no data rows".to_string());
+    }
+
+    let labels: Vec<&str> = table.rows.iter().map(|r| r[x_col].as_str()).collect();
+    let series: Vec<(&str, Vec<f64>)> = y_cols.iter()
+        .map(|&col| {
+            let values = table.rows.iter()
+                .enumerate()
+                .map(|(i, r)| parse_number(&r[col])
+                    .ok_or_else(|| format!("`{}` in row {} of `{}` is not a number", r[col], i + 2, table.headers[col])))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((table.headers[col].as_str(), values))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let all_values = series.iter().flat_map(|(_, v)| v.iter().copied());
+    let min = all_values.clone().fold(f64::INFINITY, f64::min);
+    let max = all_values.fold(f64::NEG_INFINITY, f64::max);
+    // The value axis always includes zero
+    let (axis_min, axis_max, step) = nice_scale(min.min(0.0), max.max(0.0));
+
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let y_pos = |v: f64| MARGIN_TOP + plot_height * (1.0 - (v - axis_min) / (axis_max - axis_min));
+    let slot = plot_width / labels.len() as f64;
+    let x_pos = |i: usize| MARGIN_LEFT + slot * (i as f64 + 0.5);
+
+    let title = options.title.clone().unwrap_or_else(|| {
+        let names: Vec<&str> = series.iter().map(|(name, _)| *name).collect();
+        format!("{} by {}", names.join(", "), table.headers[x_col])
+    });
+    let kind = match options.chart_type {
+        ChartType::Line => "Line",
+        ChartType::Bar => "Bar",
+    };
+    let description = format!(
+        "{} chart with {} {} from {} to {}. Values range from {} to {}.",
+        kind,
+        labels.len(),
+        if labels.len() == 1 { "point" } else { "points" },
+        labels[0],
+        labels[labels.len() - 1],
+        format_value(min),
+        format_value(max)
+    );
+
+    let mut svg = format!(
+        "<svg class=\"chart chart-{}\" viewBox=\"0 0 {} {}\" role=\"img\" aria-labelledby=\"{id}-title {id}-desc\">\
+<title id=\"{id}-title\">{}</title><desc id=\"{id}-desc\">{}</desc>",
+        kind.to_lowercase(), WIDTH, HEIGHT,
+        html_escape(&title), html_escape(&description),
+        id = id
+    );
+
+    // Grid lines and y axis labels
+    svg.push_str("<g class=\"chart-grid\">");
+    let mut tick = axis_min;
+    while tick <= axis_max + step / 2.0 {
+        let y = y_pos(tick);
+        svg.push_str(&format!(
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" />\
+<text class=\"chart-y-label\" x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            MARGIN_LEFT, y, WIDTH - MARGIN_RIGHT, y,
+            MARGIN_LEFT - 8.0, y + 4.0, format_value(tick)
+        ));
+        tick += step;
+    }
+    svg.push_str("</g>");
+
+    // X axis labels
+    let label_every = labels.len().div_ceil(MAX_X_LABELS);
+    svg.push_str("<g class=\"chart-x-labels\">");
+    for (i, label) in labels.iter().enumerate().filter(|(i, _)| i % label_every == 0) {
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            x_pos(i), HEIGHT - MARGIN_BOTTOM + 20.0, html_escape(label)
+        ));
+    }
+    svg.push_str("</g>");
+
+    // Data
+    for (s, (name, values)) in series.iter().enumerate() {
+        svg.push_str(&format!("<g class=\"chart-series chart-series-{}\">", s % 6));
+
+        match options.chart_type {
+            ChartType::Line => {
+                let points: Vec<String> = values.iter().enumerate()
+                    .map(|(i, v)| format!("{:.1},{:.1}", x_pos(i), y_pos(*v)))
+                    .collect();
+                svg.push_str(&format!("<polyline points=\"{}\" />", points.join(" ")));
+
+                for (i, v) in values.iter().enumerate() {
+                    svg.push_str(&format!(
+                        "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\"><title>{}: {}, {}</title></circle>",
+                        x_pos(i), y_pos(*v), html_escape(name), html_escape(labels[i]), format_value(*v)
+                    ));
+                }
+            }
+            ChartType::Bar => {
+                let group_width = slot * 0.8;
+                let bar_width = group_width / series.len() as f64;
+                let zero = y_pos(0.0_f64.clamp(axis_min, axis_max));
+
+                for (i, v) in values.iter().enumerate() {
+                    let x = x_pos(i) - group_width / 2.0 + bar_width * s as f64;
+                    let y = y_pos(*v);
+                    svg.push_str(&format!(
+                        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"><title>{}: {}, {}</title></rect>",
+                        x, y.min(zero), bar_width, (zero - y).abs(),
+                        html_escape(name), html_escape(labels[i]), format_value(*v)
+                    ));
+                }
+            }
+        }
+
+        svg.push_str("</g>");
+    }
+
+    // Legend, when there's more than one series
+    if series.len() > 1 {
+        svg.push_str("<g class=\"chart-legend\">");
+        let mut x = MARGIN_LEFT;
+        for (s, (name, _)) in series.iter().enumerate() {
+            svg.push_str(&format!(
+                "<g class=\"chart-series chart-series-{}\"><rect x=\"{:.1}\" y=\"8\" width=\"12\" height=\"12\" /></g>\
+<text x=\"{:.1}\" y=\"18\">{}</text>",
+                s % 6, x, x + 16.0, html_escape(name)
+            ));
+            x += 28.0 + name.chars().count() as f64 * 7.0;
+        }
+        svg.push_str("</g>");
+    }
+
+    svg.push_str("</svg>");
+
+    Ok(format!(
+        "<figure class=\"chart-figure\">{}<figcaption>{}</figcaption>\
+<details class=\"chart-data\"><summary>Show data</summary>{}</details></figure>",
+        svg,
+        html_escape(&title),
+        render_table(table, None)
+    ))
+}
+
+// Round the axis out to tidy numbers: 0-100 in steps of 20, not 0-93.7
+fn nice_scale(min: f64, max: f64) -> (f64, f64, f64) {
+    if (max - min).abs() < f64::EPSILON {
+        return (min - 1.0, max + 1.0, 0.5);
+    }
+
+    let raw_step = (max - min) / Y_TICKS as f64;
+    let magnitude = 10_f64.powf(raw_step.log10().floor());
+    let step = [1.0, 2.0, 2.5, 5.0, 10.0].iter()
+        .map(|m| m * magnitude)
+        .find(|s| *s >= raw_step)
+        .unwrap_or(10.0 * magnitude);
+
+    ((min / step).floor() * step, (max / step).ceil() * step, step)
+}
+
+fn format_value(value: f64) -> String {
+    if value.fract().abs() < 1e-9 {
+        format!("{}", value as i64)
+    } else {
+        let text = format!("{:.2}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+// Resolve a `file=` path from a data block, relative to the post's directory
+pub fn resolve_data_file(source_path: Option<&str>, file: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(file);
+    if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err(format!("data file `{}` must be beside the post or in a folder below it", file));
+    }
+
+    let dir = source_path.and_then(|p| Path::new(p).parent())
+        .ok_or_else(|| format!("can't resolve data file `{}` without the post's path", file))?;
+
+    Ok(dir.join(relative))
+}
+
+// ("csv" | "chart", arguments) for a data block's info string
+pub fn data_block_kind(info: &str) -> Option<(&str, &str)> {
+    let info = info.trim();
+    let (kind, args) = info.split_once(char::is_whitespace).unwrap_or((info, ""));
+
+    matches!(kind, "csv" | "chart").then_some((kind, args.trim()))
+}
+
+// `file=` values of the ```csv and ```chart blocks in a post, read the same
+// way the `data_blocks` transform reads them
+pub fn find_data_files(content: &str) -> Vec<String> {
+    content.lines()
+        .filter_map(|line| {
+            let (_, len) = code_fence(line)?;
+            data_block_kind(&line.trim_start()[len..])
+        })
+        .flat_map(|(_, args)| tokenize(args))
+        .filter_map(|attribute| attribute.strip_prefix("file=").map(|file| unquote(file).to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::markdown::{markdown_to_html, render_markdown};
+    use crate::utils::transforms::{trusted_placeholder, RenderContext};
+    use super::*;
+
+    #[test]
+    fn numbers_must_be_finite() {
+        assert_eq!(parse_number(" 1,200 "), Some(1200.0));
+        assert_eq!(parse_number("12.5%"), Some(12.5));
+        for value in ["NaN", "inf", "-infinity", "", "n/a"] {
+            assert_eq!(parse_number(value), None, "{}", value);
+        }
+
+        let table = parse_csv("month,users\nJan,120\nFeb,NaN\n").unwrap();
+        let options = ChartOptions { chart_type: ChartType::Line, title: None, x: None, y: vec!["users".to_string()] };
+        assert!(render_chart(&table, &options, "chart-1").unwrap_err().contains("`NaN` in row 3"));
+    }
+
+    #[test]
+    fn charts_survive_sanitising_but_raw_svg_does_not() {
+        let html = markdown_to_html("```chart type=bar\nmonth,users\nJan,120\nFeb,180\n```\n");
+        assert!(html.contains("<svg class=\"chart chart-bar\""), "{}", html);
+        assert!(html.contains("<rect x="), "{}", html);
+        assert!(!html.contains('\u{FFFC}'), "{}", html);
+
+        let html = markdown_to_html("<svg><rect x=\"0\" /><text>hi</text></svg>\n");
+        assert!(!html.contains("<svg") && !html.contains("<rect") && !html.contains("hi"), "{}", html);
+    }
+
+    #[test]
+    fn posts_cant_forge_chart_placeholders() {
+        let mut ctx = RenderContext::new(&[]);
+        let placeholder = trusted_placeholder(0, 0);
+        let forged = format!("{}\n\n```chart type=bar\nmonth,users\nJan,120\n```\n\n{}\n", placeholder, placeholder);
+        let html = render_markdown(&forged, false, &mut ctx).html;
+
+        // The chart stays where the block was, and the forged text stays text
+        let chart = html.find("<svg").unwrap();
+        assert!(html[..chart].contains(&placeholder) && html[chart..].contains(&placeholder), "{}", html);
+        assert_eq!(html.matches("<svg").count(), 1, "{}", html);
+    }
+
+    #[test]
+    fn data_files_need_a_data_block() {
+        let content = "```csv file=a.csv\n```\n```chart type=bar file=\"b c.csv\"\n```\n~~~csv file=d.csv\n~~~\n\
+            ```csvx file=no.csv\n```\n```charts file=no.csv\n```\n";
+
+        assert_eq!(find_data_files(content), ["a.csv", "b c.csv", "d.csv"]);
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use serde_json::Value;
+use leptos::logging::log;
+use crate::utils::markdown::{html_escape, markdown_options};
+use crate::utils::site_config::get_site_config;
+
+// A work that posts can cite, from the site bibliography file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Reference {
+    pub key: String,
+    // Family names first: "Smith, Jane"
+    pub authors: Vec<String>,
+    pub title: String,
+    pub year: Option<String>,
+    // Journal, book, conference or publisher
+    pub container: Option<String>,
+    pub url: Option<String>,
+    pub doi: Option<String>,
+}
+
+impl Reference {
+    // "Smith", "Smith & Jones" or "Smith et al.", for author-date citations
+    pub fn short_authors(&self) -> String {
+        let family = |author: &String| author.split(',').next().unwrap_or(author).trim().to_string();
+
+        match self.authors.as_slice() {
+            [] => self.title.clone(),
+            [one] => family(one),
+            [first, second] => format!("{} & {}", family(first), family(second)),
+            [first, ..] => format!("{} et al.", family(first)),
+        }
+    }
+
+    // The full reference as HTML: Authors (Year). <em>Title</em>. Container. Link
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+
+        if !self.authors.is_empty() {
+            html.push_str(&html_escape(&join_authors(&self.authors)));
+            html.push(' ');
+        }
+
+        html.push_str(&format!("({}). ", html_escape(self.year.as_deref().unwrap_or("n.d."))));
+        html.push_str(&format!("<em>{}</em>.", html_escape(&self.title)));
+
+        if let Some(container) = &self.container {
+            html.push_str(&format!(" {}.", html_escape(container)));
+        }
+
+        let link = self.doi.as_ref()
+            .map(|doi| format!("https://doi.org/{}", doi.trim_start_matches("https://doi.org/")))
+            .or_else(|| self.url.clone())
+            .filter(|url| url.starts_with("https://") || url.starts_with("http://"));
+
+        if let Some(link) = link {
+            html.push_str(&format!(" <a href=\"{0}\">{0}</a>", html_escape(&link)));
+        }
+
+        html
+    }
+}
+
+// "Smith, J., Doe, A., & Lee, K."
+fn join_authors(authors: &[String]) -> String {
+    let names: Vec<String> = authors.iter().map(|a| with_initials(a)).collect();
+
+    match names.as_slice() {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{}, & {}", rest.join(", "), last),
+    }
+}
+
+// "Smith, Jane Ann" -> "Smith, J. A."
+fn with_initials(author: &str) -> String {
+    match author.split_once(',') {
+        Some((family, given)) => {
+            let initials: Vec<String> = given.split_whitespace()
+                .filter_map(|name| name.chars().next())
+                .map(|c| format!("{}.", c))
+                .collect();
+
+            if initials.is_empty() {
+                family.trim().to_string()
+            } else {
+                format!("{}, {}", family.trim(), initials.join(" "))
+            }
+        }
+        None => author.to_string(),
+    }
+}
+
+// A `[@key]` or `[@key, p. 12; @other]` citation found in text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Citation {
+    pub start: usize,
+    pub end: usize,
+    // Cited keys with their optional locator ("p. 12")
+    pub items: Vec<(String, Option<String>)>,
+}
+
+// Find pandoc-style bracketed citations in a run of text
+pub fn find_citations(text: &str) -> Vec<Citation> {
+    static CITATION_RE: OnceLock<Regex> = OnceLock::new();
+    static KEY_RE: OnceLock<Regex> = OnceLock::new();
+    let citation_re = CITATION_RE.get_or_init(|| Regex::new(r"\[(@[^\[\]]+)\]").unwrap());
+    let key_re = KEY_RE.get_or_init(|| Regex::new(r"^@([\w:.#$%&+?<>~/-]+)\s*(?:,\s*(.+))?$").unwrap());
+
+    citation_re.captures_iter(text)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let items = caps[1].split(';')
+                .map(|part| {
+                    let item = key_re.captures(part.trim())?;
+                    let key = item[1].trim_end_matches(['.', ':']).to_string();
+                    let locator = item.get(2).map(|l| l.as_str().trim().to_string());
+                    Some((key, locator))
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(Citation { start: whole.start(), end: whole.end(), items })
+        })
+        .collect()
+}
+
+// Keys cited in `content` that aren't in the bibliography, with their line
+// numbers. Like the `citations` transform, this ignores code and link text.
+pub fn find_unknown_citations(content: &str) -> Vec<(usize, String)> {
+    unknown_citations(content, get_bibliography())
+}
+
+fn unknown_citations(content: &str, bibliography: &HashMap<String, Reference>) -> Vec<(usize, String)> {
+    let mut unknown = Vec::new();
+    let mut skip_depth = 0;
+    // Adjacent text is merged, as the parser splits it at brackets, and
+    // numbered by the line it starts on
+    let mut run: Option<(usize, String)> = None;
+    let mut check = |(start, text): (usize, String)| {
+        let line = content[..start].matches('\n').count() + 1;
+        for citation in find_citations(&text) {
+            for (key, _) in citation.items {
+                if !bibliography.contains_key(&key) {
+                    unknown.push((line, key));
+                }
+            }
+        }
+    };
+
+    for (event, range) in Parser::new_ext(content, markdown_options()).into_offset_iter() {
+        match event {
+            Event::Text(text) if skip_depth == 0 => {
+                match run.as_mut() {
+                    Some((_, run)) => run.push_str(&text),
+                    None => run = Some((range.start, text.to_string())),
+                }
+                continue;
+            }
+            Event::Start(Tag::CodeBlock(_)) | Event::Start(Tag::Link(..)) => skip_depth += 1,
+            Event::End(Tag::CodeBlock(_)) | Event::End(Tag::Link(..)) => skip_depth -= 1,
+            _ => {}
+        }
+        if let Some(run) = run.take() {
+            check(run);
+        }
+    }
+    if let Some(run) = run.take() {
+        check(run);
+    }
+
+    unknown
+}
+
+// Load references from a BibTeX or CSL-JSON file, chosen by extension
+pub fn load_bibliography(path: &str) -> HashMap<String, Reference> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+
+    let references = if path.ends_with(".json") {
+        parse_csl_json(&content)
+    } else {
+        parse_bibtex(&content)
+    };
+
+    log!("Loaded {} references from {}", references.len(), path);
+
+    references.into_iter()
+        .map(|r| (r.key.clone(), r))
+        .collect()
+}
+
+fn parse_csl_json(content: &str) -> Vec<Reference> {
+    let items: Vec<Value> = match serde_json::from_str(content) {
+        Ok(items) => items,
+        Err(e) => {
+            log!("Invalid CSL-JSON bibliography: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let text = |item: &Value, field: &str| item.get(field).and_then(Value::as_str).map(str::to_string);
+
+    items.iter()
+        .filter_map(|item| {
+            let key = text(item, "id")?;
+
+            let authors = item.get("author")
+                .and_then(Value::as_array)
+                .map(|authors| authors.iter()
+                    .filter_map(|a| match (text(a, "family"), text(a, "given")) {
+                        (Some(family), Some(given)) => Some(format!("{}, {}", family, given)),
+                        (Some(family), None) => Some(family),
+                        _ => text(a, "literal"),
+                    })
+                    .collect())
+                .unwrap_or_default();
+
+            let year = item.pointer("/issued/date-parts/0/0")
+                .map(|y| y.to_string().trim_matches('"').to_string());
+
+            Some(Reference {
+                key,
+                authors,
+                title: text(item, "title").unwrap_or_default(),
+                year,
+                container: text(item, "container-title").or_else(|| text(item, "publisher")),
+                url: text(item, "URL"),
+                doi: text(item, "DOI"),
+            })
+        })
+        .collect()
+}
+
+fn parse_bibtex(content: &str) -> Vec<Reference> {
+    let mut references = Vec::new();
+    let mut rest = content;
+
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+
+        let Some(open) = rest.find(['{', '(']) else {
+            break;
+        };
+        let entry_type = rest[..open].trim().to_lowercase();
+        let Some(close) = matching_close(rest, open) else {
+            break;
+        };
+        let body = &rest[open + 1..close];
+        rest = &rest[close + 1..];
+
+        if matches!(entry_type.as_str(), "string" | "comment" | "preamble") {
+            continue;
+        }
+
+        let Some((key, fields)) = body.split_once(',') else {
+            continue;
+        };
+        let fields = parse_bibtex_fields(fields);
+        let field = |name: &str| fields.get(name).cloned();
+
+        let authors = field("author")
+            .or_else(|| field("editor"))
+            .map(|names| names.split(" and ").map(normalize_bibtex_name).collect())
+            .unwrap_or_default();
+
+        references.push(Reference {
+            key: key.trim().to_string(),
+            authors,
+            title: field("title").unwrap_or_default(),
+            year: field("year").or_else(|| field("date").map(|d| d.chars().take(4).collect())),
+            container: field("journal")
+                .or_else(|| field("booktitle"))
+                .or_else(|| field("publisher"))
+                .or_else(|| field("howpublished")),
+            url: field("url"),
+            doi: field("doi"),
+        });
+    }
+
+    references
+}
+
+// Index of the bracket closing the one at `open`, allowing nesting
+fn matching_close(text: &str, open: usize) -> Option<usize> {
+    let (open_char, close_char) = match text[open..].chars().next()? {
+        '(' => ('(', ')'),
+        _ => ('{', '}'),
+    };
+    let mut depth = 0;
+
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            c if c == open_char => depth += 1,
+            c if c == close_char => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// `name = {value}`, `name = "value"` or `name = 2020`, separated by commas
+fn parse_bibtex_fields(body: &str) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    let mut rest = body;
+
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim().trim_start_matches(',').trim().to_lowercase();
+        let value_start = rest[eq + 1..].trim_start();
+
+        let (value, remaining) = match value_start.chars().next() {
+            Some('{') => match matching_close(value_start, 0) {
+                Some(close) => (&value_start[1..close], &value_start[close + 1..]),
+                None => break,
+            },
+            Some('"') => match value_start[1..].find('"') {
+                Some(close) => (&value_start[1..close + 1], &value_start[close + 2..]),
+                None => break,
+            },
+            _ => {
+                let end = value_start.find(',').unwrap_or(value_start.len());
+                (value_start[..end].trim(), &value_start[end..])
+            }
+        };
+
+        // `--` is an en dash in text and page ranges, but not in links
+        let value = clean_bibtex_value(value);
+        let value = match name.as_str() {
+            "url" | "doi" | "eprint" | "file" => value,
+            _ => value.replace("--", "–"),
+        };
+
+        fields.insert(name, value);
+        rest = remaining;
+    }
+
+    fields
+}
+
+// Drop grouping braces and common LaTeX escapes
+fn clean_bibtex_value(value: &str) -> String {
+    let value = value.replace(['{', '}'], "")
+        .replace("\\&", "&")
+        .replace("\\%", "%")
+        .replace("\\_", "_");
+
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// "Jane Smith" -> "Smith, Jane"; "Smith, Jane" is kept as it is
+fn normalize_bibtex_name(name: &str) -> String {
+    let name = name.trim();
+
+    if name.contains(',') {
+        return name.to_string();
+    }
+
+    match name.rsplit_once(' ') {
+        Some((given, family)) => format!("{}, {}", family, given),
+        None => name.to_string(),
+    }
+}
+
+// The site bibliography configured in site.json, loaded on first use
+pub fn get_bibliography() -> &'static HashMap<String, Reference> {
+    static BIBLIOGRAPHY: OnceLock<HashMap<String, Reference>> = OnceLock::new();
+
+    BIBLIOGRAPHY.get_or_init(|| load_bibliography(&get_site_config().citations.bibliography))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keys_and_locators() {
+        let citations = find_citations("As shown [@smith2020, p. 12; @doe:2019.] and [see me] or [@].");
+
+        assert_eq!(citations.len(), 1);
+        assert_eq!(citations[0].items, [
+            ("smith2020".to_string(), Some("p. 12".to_string())),
+            ("doe:2019".to_string(), None),
+        ]);
+        assert_eq!(citations[0].start, 9);
+    }
+
+    #[test]
+    fn ignores_citations_in_code_and_links() {
+        let bibliography: HashMap<String, Reference> = [("known", "Known")].into_iter()
+            .map(|(key, title)| (key.to_string(), Reference { key: key.to_string(), title: title.to_string(), ..Reference::default() }))
+            .collect();
+        let content = "[@one] and [@known]\n```\n[@two]\n~~~\n[@three]\n```\n~~~ text\n[@four]\n```\n[@five]\n~~~\n\
+            [@six], `[@seven]`, [a link [@eight]](/x)\n\n    [@nine]\n";
+
+        assert_eq!(unknown_citations(content, &bibliography), [(1, "one".to_string()), (12, "six".to_string())]);
+    }
+
+    #[test]
+    fn bibtex_dashes_only_in_text() {
+        let references = parse_bibtex("@article{key,\n  title = {Rust {--} a history},\n  journal = \"J. of A--B\",\n  \
+            url = {https://example.com/a--b},\n  doi = {10.1000/x--y},\n  year = 2020\n}");
+
+        assert_eq!(references[0].title, "Rust – a history");
+        assert_eq!(references[0].container.as_deref(), Some("J. of A–B"));
+        assert_eq!(references[0].url.as_deref(), Some("https://example.com/a--b"));
+        assert_eq!(references[0].doi.as_deref(), Some("10.1000/x--y"));
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use crate::model::{get_all_areas, JDArea, JDCategory, Project};
+use crate::utils::facets::FacetSelection;
+use crate::utils::format::format_date;
+use crate::utils::fuzzy::{Vocabulary, VocabularyBuilder};
+use crate::utils::markdown::markdown_to_plain_text;
+use crate::utils::search::terms;
+use crate::utils::search_query::{SearchQuery, Searchable};
+
+// What the search island knows about each post: enough to match and list it,
+// without the post's content
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexedPost {
+    pub slug: String,
+    pub title: String,
+    pub summary: String,
+    pub tags: Vec<String>,
+    pub jd_identifier: String,
+    pub jd_category: Option<JDCategory>,
+    // YYYY-MM-DD
+    pub published: String,
+    // Distinct stemmed terms from the title, summary, tags, category, headings
+    // and body, sorted
+    pub terms: Vec<String>,
+}
+
+impl Searchable for IndexedPost {
+    fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    fn category(&self) -> Option<&JDCategory> {
+        self.jd_category.as_ref()
+    }
+
+    fn published(&self) -> String {
+        self.published.clone()
+    }
+
+    fn has_term(&self, term: &str) -> bool {
+        self.terms.binary_search_by(|t| t.as_str().cmp(term)).is_ok()
+    }
+
+    // The index has no word positions, so in the browser a phrase matches
+    // when all of its words appear; /api/search checks that they're adjacent
+    fn contains_phrase(&self, phrase: &[String]) -> bool {
+        phrase.iter().all(|term| self.has_term(term))
+    }
+}
+
+// The compact search index the search island fetches, newest posts first,
+// with the words they use for completing and correcting queries
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ClientIndex {
+    pub posts: Vec<IndexedPost>,
+    pub vocabulary: Vocabulary,
+    // For naming areas in the facet panel
+    pub areas: Vec<JDArea>,
+}
+
+impl ClientIndex {
+    pub fn build(projects: &[Project]) -> ClientIndex {
+        let mut vocabulary = VocabularyBuilder::default();
+        let mut posts: Vec<IndexedPost> = projects.iter()
+            .map(|project| {
+                let text = indexed_text(project);
+                vocabulary.add_post(&text, &project.tech_stack);
+                indexed_post(project, &text)
+            })
+            .collect();
+        posts.sort_by(|a, b| b.published.cmp(&a.published));
+
+        ClientIndex { posts, vocabulary: vocabulary.build(), areas: get_all_areas() }
+    }
+
+    // Posts matching every clause of a query, newest first
+    pub fn search(&self, query: &SearchQuery) -> Vec<&IndexedPost> {
+        self.posts.iter()
+            .filter(|post| query.matches(*post))
+            .collect()
+    }
+
+    // What the search island shows for a search box and facet choices, for
+    // rendering the results on the server. Terms are left out, since the
+    // results are only listed.
+    pub fn results(&self, search_box: &str, facets: &FacetSelection) -> Vec<IndexedPost> {
+        let mut query = SearchQuery::parse(search_box);
+        query.expand(&self.vocabulary);
+
+        self.search(&query).into_iter()
+            .filter(|post| facets.matches(*post))
+            .map(|post| IndexedPost { terms: Vec::new(), ..post.clone() })
+            .collect()
+    }
+}
+
+// Everything a post can be found by: its title, summary, tags, category,
+// headings and body
+fn indexed_text(project: &Project) -> String {
+    let mut text = vec![project.title.clone(), project.summary.clone(), project.tech_stack.join(" ")];
+
+    if let Some(category) = &project.jd_category {
+        text.push(category.name.clone());
+        text.push(category.description.clone());
+    }
+    match &project.rendered {
+        Some(rendered) => {
+            text.extend(rendered.toc.iter().map(|entry| entry.text.clone()));
+            text.push(rendered.plain_text.clone());
+        }
+        None => text.push(markdown_to_plain_text(&project.content)),
+    }
+
+    text.join("\n")
+}
+
+fn indexed_post(project: &Project, text: &str) -> IndexedPost {
+    let mut post_terms = terms(text);
+    post_terms.sort();
+    post_terms.dedup();
+
+    IndexedPost {
+        slug: project.slug.clone(),
+        title: project.title.clone(),
+        summary: project.summary.clone(),
+        tags: project.tech_stack.clone(),
+        jd_identifier: project.jd_identifier.clone(),
+        jd_category: project.jd_category.clone(),
+        published: format_date(project.created_at),
+        terms: post_terms,
+    }
+}
+
+// Where the island asks for the current index when the one named in its
+// page has gone (the posts changed since the page was rendered)
+pub const CURRENT_INDEX_URL: &str = "/search/current.json";
+
+// The body served at `CURRENT_INDEX_URL`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CurrentClientIndex {
+    pub url: String,
+}
+
+// The index, serialised, and the file it's served as. The file name carries
+// a hash of the contents, so browsers can cache it indefinitely.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientIndexAsset {
+    pub file_name: String,
+    pub json: String,
+    pub index: ClientIndex,
+}
+
+impl ClientIndexAsset {
+    pub fn url(&self) -> String {
+        format!("/search/{}", self.file_name)
+    }
+}
+
+// The asset for the current content, rebuilt only when it changes
+static ASSET: Mutex<Option<(u64, Arc<ClientIndexAsset>)>> = Mutex::new(None);
+
+pub fn client_index(projects: &[Project]) -> Arc<ClientIndexAsset> {
+    let mut hasher = DefaultHasher::new();
+    for project in projects {
+        project.slug.hash(&mut hasher);
+        project.title.hash(&mut hasher);
+        project.summary.hash(&mut hasher);
+        project.tech_stack.hash(&mut hasher);
+        project.jd_identifier.hash(&mut hasher);
+        project.jd_category.as_ref().map(|c| (c.id, c.area_id, &c.name, &c.description)).hash(&mut hasher);
+        // Only the date is indexed; undated posts get the load time, which
+        // would change the hash on every reload
+        format_date(project.created_at).hash(&mut hasher);
+        project.content.hash(&mut hasher);
+        project.rendered.as_ref()
+            .map(|r| (&r.plain_text, r.toc.iter().map(|entry| &entry.text).collect::<Vec<_>>()))
+            .hash(&mut hasher);
+    }
+    for area in get_all_areas() {
+        (area.id, area.name).hash(&mut hasher);
+    }
+    let hash = hasher.finish();
+
+    let mut cached = ASSET.lock().unwrap();
+    if let Some((cached_hash, asset)) = cached.as_ref() {
+        if *cached_hash == hash {
+            return asset.clone();
+        }
+    }
+
+    let index = ClientIndex::build(projects);
+    let json = serde_json::to_string(&index).unwrap_or_default();
+    let mut hasher = DefaultHasher::new();
+    json.hash(&mut hasher);
+
+    let asset = Arc::new(ClientIndexAsset {
+        file_name: format!("index.{:016x}.json", hasher.finish()),
+        json,
+        index,
+    });
+    *cached = Some((hash, asset.clone()));
+    asset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(slug: &str, tags: &[&str], terms: &[&str]) -> IndexedPost {
+        IndexedPost {
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            summary: String::new(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            jd_identifier: String::new(),
+            jd_category: None,
+            published: "2025-01-01".to_string(),
+            terms: terms.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn results_apply_the_query_and_facets() {
+        let index = ClientIndex {
+            posts: vec![post("a", &["rust"], &["macro"]), post("b", &["go"], &["macro"]), post("c", &["rust"], &["async"])],
+            ..ClientIndex::default()
+        };
+        let rust = FacetSelection::from_params(|key| (key == "tag").then(|| "rust".to_string()));
+
+        let slugs = |results: Vec<IndexedPost>| results.into_iter().map(|p| p.slug).collect::<Vec<_>>();
+        assert_eq!(slugs(index.results("macro", &FacetSelection::default())), ["a", "b"]);
+        assert_eq!(slugs(index.results("macro", &rust)), ["a"]);
+        assert_eq!(slugs(index.results("", &rust)), ["a", "c"]);
+        assert!(index.results("", &rust).iter().all(|p| p.terms.is_empty()));
+    }
+}
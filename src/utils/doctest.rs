@@ -0,0 +1,363 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use regex::Regex;
+use crate::utils::jd_content_loader::{extract_jd_info_from_path, load_markdown_files};
+use crate::utils::markdown::code_fence;
+use crate::utils::snippets::SnippetInclude;
+
+// How long a compiled example may run before it counts as a failure
+const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Exit code of a Rust program that panicked; `should_panic` examples must
+// exit with it, like rustdoc requires
+const PANIC_EXIT_CODE: i32 = 101;
+
+// Rustdoc-style code block attributes: ```rust,no_run or ```rust should_panic
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocTestAttributes {
+    pub ignore: bool,
+    pub no_run: bool,
+    pub should_panic: bool,
+    pub compile_fail: bool,
+    pub edition: Option<String>,
+}
+
+// A ```rust block from a post
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocTest {
+    pub path: PathBuf,
+    // Line of the opening fence in the post file
+    pub line: usize,
+    pub code: String,
+    // Whether the code came from a snippet file rather than the post itself
+    pub included: bool,
+    pub attributes: DocTestAttributes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocTestOutcome {
+    Passed,
+    Ignored,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DocTestOptions {
+    // Run examples after compiling them (unless marked `no_run`)
+    pub run: bool,
+    // Only check posts in these categories (all posts when empty)
+    pub categories: Vec<u8>,
+}
+
+// Parse a fence info string. Returns `None` for blocks that aren't Rust.
+// Like rustdoc, attributes can be separated by commas or spaces.
+pub fn parse_doctest_info(info: &str) -> Option<DocTestAttributes> {
+    let mut tokens = info.split([',', ' ', '\t']).map(str::trim).filter(|t| !t.is_empty());
+
+    if tokens.next()? != "rust" {
+        return None;
+    }
+
+    let mut attributes = DocTestAttributes::default();
+    for token in tokens {
+        match token {
+            "ignore" => attributes.ignore = true,
+            "no_run" => attributes.no_run = true,
+            "should_panic" => attributes.should_panic = true,
+            "compile_fail" => attributes.compile_fail = true,
+            t if t.starts_with("edition") => attributes.edition = Some(t["edition".len()..].to_string()),
+            _ => {}
+        }
+    }
+
+    Some(attributes)
+}
+
+// Every ```rust (or ~~~rust) block in a post file. Line numbers count from
+// the top of the file, front matter included, so they match what an editor shows.
+pub fn extract_doctests(path: &Path, source: &str) -> Vec<DocTest> {
+    let mut doctests = Vec::new();
+    // The opening fence, its line and info string, and the code so far
+    let mut current: Option<((char, usize), usize, &str, String)> = None;
+
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let fence = code_fence(line);
+
+        if let Some((open, start, info, mut code)) = current.take() {
+            let closes = fence.is_some_and(|(c, len)| c == open.0 && len >= open.1 && trimmed[len..].trim().is_empty());
+
+            if closes {
+                if let Some(attributes) = parse_doctest_info(info) {
+                    // Snippet includes are checked with the code they pull in
+                    let included = match SnippetInclude::parse(info).and_then(|include| include.ok()?.read().ok()) {
+                        Some(snippet) => {
+                            code = snippet;
+                            true
+                        }
+                        None => false,
+                    };
+                    doctests.push(DocTest { path: path.to_path_buf(), line: start, code, included, attributes });
+                }
+            } else {
+                code.push_str(line);
+                code.push('\n');
+                current = Some((open, start, info, code));
+            }
+        } else if let Some(open) = fence {
+            current = Some((open, i + 1, trimmed[open.1..].trim(), String::new()));
+        }
+    }
+
+    doctests
+}
+
+// Wrap the example in `fn main` unless it has one, as rustdoc does.
+// Also returns how many lines were added before the example's first line,
+// when its lines stay in order, so compiler errors can point at the post.
+fn doctest_program(code: &str) -> (String, Option<usize>) {
+    if code.contains("fn main") {
+        return (format!("#![allow(unused)]\n{}", code), Some(1));
+    }
+
+    // Crate attributes have to stay at the top
+    let (crate_attributes, body): (Vec<&str>, Vec<&str>) = code.lines()
+        .partition(|line| line.trim_start().starts_with("#!["));
+
+    let program = format!(
+        "#![allow(unused)]\n{}\nfn main() {{\n{}\n}}\n",
+        crate_attributes.join("\n"),
+        body.join("\n")
+    );
+
+    (program, crate_attributes.is_empty().then_some(3))
+}
+
+// Point `main.rs:LINE:COL` locations in compiler output at the post instead
+fn map_locations(output: &str, source: &Path, doctest: &DocTest, offset: Option<usize>) -> String {
+    let Some(offset) = offset.filter(|_| !doctest.included) else {
+        return output.to_string();
+    };
+
+    let pattern = format!(r"{}:(\d+):(\d+)", regex::escape(&source.to_string_lossy()));
+    let Ok(location_re) = Regex::new(&pattern) else {
+        return output.to_string();
+    };
+
+    location_re.replace_all(output, |caps: &regex::Captures| {
+        let line: usize = caps[1].parse().unwrap_or(0);
+        match line.checked_sub(offset) {
+            Some(code_line) if code_line > 0 => format!("{}:{}:{}", doctest.path.display(), doctest.line + code_line, &caps[2]),
+            _ => caps[0].to_string(),
+        }
+    }).to_string()
+}
+
+// Each example is built in a directory of its own, so concurrent runs
+// (and examples from the same post) never share files
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+// Compile (and optionally run) one example with the local toolchain
+pub fn run_doctest(doctest: &DocTest, options: &DocTestOptions) -> DocTestOutcome {
+    if doctest.attributes.ignore {
+        return DocTestOutcome::Ignored;
+    }
+
+    let dir = std::env::temp_dir().join(format!(
+        "content-doctest-{}-{}",
+        std::process::id(),
+        NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        return DocTestOutcome::Failed(format!("can't create {}: {}", dir.display(), e));
+    }
+
+    let outcome = build_and_run(doctest, options, &dir);
+    let _ = fs::remove_dir_all(&dir);
+    outcome
+}
+
+fn build_and_run(doctest: &DocTest, options: &DocTestOptions, dir: &Path) -> DocTestOutcome {
+    let attributes = &doctest.attributes;
+    let source = dir.join("main.rs");
+    let binary = dir.join("main");
+    let (program, offset) = doctest_program(&doctest.code);
+    if let Err(e) = fs::write(&source, program) {
+        return DocTestOutcome::Failed(format!("can't write {}: {}", source.display(), e));
+    }
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let compiled = Command::new(rustc)
+        .arg("--edition").arg(attributes.edition.as_deref().unwrap_or("2021"))
+        .arg("--crate-type").arg("bin")
+        .arg("-o").arg(&binary)
+        .arg(&source)
+        .output();
+
+    let compiled = match compiled {
+        Ok(output) => output,
+        Err(e) => return DocTestOutcome::Failed(format!("can't run rustc: {}", e)),
+    };
+
+    if attributes.compile_fail {
+        return if compiled.status.success() {
+            DocTestOutcome::Failed("compiled, but was marked compile_fail".to_string())
+        } else {
+            DocTestOutcome::Passed
+        };
+    }
+
+    if !compiled.status.success() {
+        let stderr = String::from_utf8_lossy(&compiled.stderr);
+        return DocTestOutcome::Failed(map_locations(&stderr, &source, doctest, offset));
+    }
+
+    if !options.run || attributes.no_run {
+        return DocTestOutcome::Passed;
+    }
+
+    match run_with_timeout(&binary) {
+        Err(e) => DocTestOutcome::Failed(e),
+        Ok((Some(PANIC_EXIT_CODE), _)) if attributes.should_panic => DocTestOutcome::Passed,
+        Ok((Some(0), _)) if attributes.should_panic => {
+            DocTestOutcome::Failed("ran successfully, but was marked should_panic".to_string())
+        }
+        Ok((code, stderr)) if attributes.should_panic => DocTestOutcome::Failed(format!(
+            "{} without panicking, but was marked should_panic\n{}",
+            code.map_or("was killed".to_string(), |code| format!("exited with code {}", code)),
+            stderr
+        )),
+        Ok((Some(0), _)) => DocTestOutcome::Passed,
+        Ok((_, stderr)) => DocTestOutcome::Failed(stderr),
+    }
+}
+
+// The program's exit code (none if it was killed by a signal), and what it
+// wrote to stderr
+fn run_with_timeout(binary: &Path) -> Result<(Option<i32>, String), String> {
+    let mut child = Command::new(binary)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("can't run example: {}", e))?;
+
+    // Drain stderr while waiting so a chatty example can't fill the pipe and stall
+    let mut stderr = child.stderr.take();
+    let reader = thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(stderr) = stderr.as_mut() {
+            let _ = stderr.read_to_string(&mut output);
+        }
+        output
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() > RUN_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {}s", RUN_TIMEOUT.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+
+    Ok((status.code(), reader.join().unwrap_or_default()))
+}
+
+// Check every Rust block in the posts under `root_dir`
+pub fn run_content_doctests(root_dir: &str, options: &DocTestOptions) -> Vec<(DocTest, DocTestOutcome)> {
+    let mut results = Vec::new();
+
+    for (path, _, _) in load_markdown_files(root_dir) {
+        let (_, category, _) = extract_jd_info_from_path(&path);
+        if !options.categories.is_empty() && !category.is_some_and(|c| options.categories.contains(&c)) {
+            continue;
+        }
+
+        // Read the file again so line numbers include the front matter
+        let Ok(source) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        for doctest in extract_doctests(&path, &source) {
+            let outcome = run_doctest(&doctest, options);
+            results.push((doctest, outcome));
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doctest(code: &str) -> DocTest {
+        DocTest {
+            path: PathBuf::from("content/post.md"),
+            line: 10,
+            code: code.to_string(),
+            included: false,
+            attributes: DocTestAttributes::default(),
+        }
+    }
+
+    #[test]
+    fn parses_rustdoc_attributes() {
+        assert_eq!(parse_doctest_info("python"), None);
+        assert_eq!(parse_doctest_info(""), None);
+        assert_eq!(parse_doctest_info("rust"), Some(DocTestAttributes::default()));
+        assert_eq!(parse_doctest_info("rust,ignore"), Some(DocTestAttributes { ignore: true, ..Default::default() }));
+        assert_eq!(
+            parse_doctest_info("rust should_panic, no_run edition2018"),
+            Some(DocTestAttributes { no_run: true, should_panic: true, edition: Some("2018".to_string()), ..Default::default() })
+        );
+        assert_eq!(parse_doctest_info("rust,compile_fail").map(|a| a.compile_fail), Some(true));
+    }
+
+    #[test]
+    fn finds_backtick_and_tilde_blocks() {
+        let source = "---\ntitle: Post\n---\n```rust\nlet a = 1;\n```\n\n~~~rust,no_run\nlet b = 2;\n```\nstill b\n~~~\n\n\
+            ````markdown\n```rust\nnot a test\n```\n````\n\n```python\nx = 1\n```\n";
+        let doctests = extract_doctests(Path::new("post.md"), source);
+
+        assert_eq!(doctests.len(), 2);
+        assert_eq!((doctests[0].line, doctests[0].code.as_str()), (4, "let a = 1;\n"));
+        assert_eq!((doctests[1].line, doctests[1].code.as_str()), (8, "let b = 2;\n```\nstill b\n"));
+        assert!(doctests[1].attributes.no_run);
+    }
+
+    #[test]
+    fn wraps_examples_in_main() {
+        assert_eq!(doctest_program("fn main() {}\n"), ("#![allow(unused)]\nfn main() {}\n".to_string(), Some(1)));
+        assert_eq!(doctest_program("let a = 1;"), ("#![allow(unused)]\n\nfn main() {\nlet a = 1;\n}\n".to_string(), Some(3)));
+        // Crate attributes are moved above `main`, so lines no longer line up
+        assert_eq!(doctest_program("#![deny(warnings)]\nlet a = 1;").1, None);
+    }
+
+    #[test]
+    fn maps_error_lines_back_to_the_post() {
+        let source = Path::new("/tmp/content-doctest-1-0/main.rs");
+        let output = "error[E0425]: cannot find value `x`\n --> /tmp/content-doctest-1-0/main.rs:5:13\n  |\n --> /tmp/content-doctest-1-0/main.rs:2:1\n";
+
+        // The example starts on line 4 of main.rs (after the wrapper's three
+        // lines) and on line 11 of the post (after the fence on line 10)
+        let mapped = map_locations(output, source, &doctest("let a = 1;\nlet b = x;"), Some(3));
+        assert_eq!(mapped, "error[E0425]: cannot find value `x`\n --> content/post.md:12:13\n  |\n --> /tmp/content-doctest-1-0/main.rs:2:1\n");
+
+        // Without a reliable offset, or for snippet files, output is left alone
+        assert_eq!(map_locations(output, source, &doctest(""), None), output);
+        let included = DocTest { included: true, ..doctest("") };
+        assert_eq!(map_locations(output, source, &included, Some(3)), output);
+    }
+}
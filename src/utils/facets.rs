@@ -0,0 +1,317 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::model::JDArea;
+use crate::utils::feeds::encode_path_segment;
+use crate::utils::search_query::Searchable;
+use crate::utils::tags::tag_matches;
+
+// The ways search results can be narrowed down, besides the query itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Facet {
+    Area,
+    Category,
+    Tag,
+    Year,
+}
+
+pub const FACETS: [Facet; 4] = [Facet::Area, Facet::Category, Facet::Tag, Facet::Year];
+
+impl Facet {
+    // The URL parameter holding the facet's selected values
+    pub fn param(self) -> &'static str {
+        match self {
+            Facet::Area => "area",
+            Facet::Category => "cat",
+            Facet::Tag => "tag",
+            Facet::Year => "year",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Facet::Area => "Area",
+            Facet::Category => "Category",
+            Facet::Tag => "Tag",
+            Facet::Year => "Year",
+        }
+    }
+
+    // A post's values for this facet: its area and category ids, tags, or
+    // publication year. Nested tags also give their parents, so "govtech/policy"
+    // is counted under "govtech" too, as `matches` would select it.
+    fn values(self, post: &impl Searchable) -> Vec<String> {
+        match self {
+            Facet::Area => post.category().map(|c| c.area_id.to_string()).into_iter().collect(),
+            Facet::Category => post.category().map(|c| c.id.to_string()).into_iter().collect(),
+            Facet::Tag => {
+                let mut tags: Vec<String> = Vec::new();
+                for tag in post.tags() {
+                    let parents = tag.match_indices('/').map(|(i, _)| &tag[..i]);
+                    for value in parents.chain([tag.as_str()]) {
+                        if !tags.iter().any(|t| t.eq_ignore_ascii_case(value)) {
+                            tags.push(value.to_string());
+                        }
+                    }
+                }
+                tags
+            }
+            Facet::Year => vec![post.published().chars().take(4).collect()],
+        }
+    }
+
+    // Tags match their nested tags too, so "govtech" covers "govtech/policy"
+    fn matches(self, value: &str, post: &impl Searchable) -> bool {
+        match self {
+            Facet::Tag => post.tags().iter().any(|t| tag_matches(t, value)),
+            _ => self.values(post).iter().any(|v| v == value),
+        }
+    }
+}
+
+// The facet values chosen to narrow the results. A post must match one of
+// the chosen values of every facet that has any.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FacetSelection {
+    pub selected: BTreeMap<Facet, Vec<String>>,
+}
+
+impl FacetSelection {
+    // Read a selection from URL parameters, e.g. `?area=10,20&tag=rust`
+    pub fn from_params(param: impl Fn(&str) -> Option<String>) -> FacetSelection {
+        let mut selection = FacetSelection::default();
+
+        for facet in FACETS {
+            let values: Vec<String> = param(facet.param())
+                .unwrap_or_default()
+                .split(',')
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect();
+
+            if !values.is_empty() {
+                selection.selected.insert(facet, values);
+            }
+        }
+
+        selection
+    }
+
+    // The URL query string for a search query and this selection, starting
+    // with `?`, or empty when there's nothing to keep
+    pub fn query_string(&self, query: &str) -> String {
+        let mut params = Vec::new();
+
+        if !query.trim().is_empty() {
+            params.push(format!("q={}", encode_path_segment(query.trim())));
+        }
+        for (facet, values) in &self.selected {
+            let values: Vec<String> = values.iter().map(|value| encode_path_segment(value)).collect();
+            params.push(format!("{}={}", facet.param(), values.join(",")));
+        }
+
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", params.join("&"))
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    pub fn is_selected(&self, facet: Facet, value: &str) -> bool {
+        self.selected.get(&facet).is_some_and(|values| values.iter().any(|v| same_value(facet, v, value)))
+    }
+
+    pub fn toggle(&mut self, facet: Facet, value: &str) {
+        let values = self.selected.entry(facet).or_default();
+
+        if values.iter().any(|v| same_value(facet, v, value)) {
+            values.retain(|v| !same_value(facet, v, value));
+        } else {
+            values.push(value.to_string());
+        }
+
+        if values.is_empty() {
+            self.selected.remove(&facet);
+        }
+    }
+
+    pub fn matches(&self, post: &impl Searchable) -> bool {
+        self.matches_except(post, None)
+    }
+
+    fn matches_except(&self, post: &impl Searchable, skip: Option<Facet>) -> bool {
+        self.selected.iter()
+            .filter(|(facet, _)| Some(**facet) != skip)
+            .all(|(facet, values)| values.iter().any(|value| facet.matches(value, post)))
+    }
+}
+
+// Tags are compared without regard to case
+fn same_value(facet: Facet, a: &str, b: &str) -> bool {
+    match facet {
+        Facet::Tag => a.eq_ignore_ascii_case(b),
+        _ => a == b,
+    }
+}
+
+// One choice in a facet panel
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetValue {
+    pub value: String,
+    pub label: String,
+    // Posts matching the query and the other facets' choices that have this value
+    pub count: usize,
+    pub selected: bool,
+}
+
+// The choices for one facet among the posts matching a query. Counts take
+// the other facets' choices into account but not this facet's own, so they
+// say how many results picking a value would give. Chosen values are always
+// listed, even when no post has them.
+pub fn facet_values<P: Searchable>(posts: &[P], selection: &FacetSelection, facet: Facet, areas: &[JDArea]) -> Vec<FacetValue> {
+    let candidates: Vec<&P> = posts.iter()
+        .filter(|post| selection.matches_except(*post, Some(facet)))
+        .collect();
+
+    let mut values: Vec<FacetValue> = Vec::new();
+    for post in &candidates {
+        for value in facet.values(*post) {
+            match values.iter_mut().find(|v| same_value(facet, &v.value, &value)) {
+                Some(existing) => existing.count += 1,
+                None => values.push(FacetValue {
+                    label: facet_label(facet, &value, *post, areas),
+                    selected: selection.is_selected(facet, &value),
+                    value,
+                    count: 1,
+                }),
+            }
+        }
+    }
+
+    for value in selection.selected.get(&facet).into_iter().flatten() {
+        if !values.iter().any(|v| same_value(facet, &v.value, value)) {
+            values.push(FacetValue {
+                value: value.clone(),
+                label: match facet {
+                    Facet::Area => area_label(value, areas),
+                    _ => value.clone(),
+                },
+                count: candidates.iter().filter(|post| facet.matches(value, **post)).count(),
+                selected: true,
+            });
+        }
+    }
+
+    match facet {
+        Facet::Area | Facet::Category => values.sort_by_key(|v| v.value.parse::<u8>().unwrap_or(u8::MAX)),
+        Facet::Tag => values.sort_by_key(|v| (Reverse(v.count), v.label.to_lowercase())),
+        Facet::Year => values.sort_by(|a, b| b.value.cmp(&a.value)),
+    }
+
+    values
+}
+
+fn facet_label(facet: Facet, value: &str, post: &impl Searchable, areas: &[JDArea]) -> String {
+    match facet {
+        Facet::Area => area_label(value, areas),
+        Facet::Category => post.category()
+            .map_or_else(|| value.to_string(), |c| format!("{} {}", c.id, c.name)),
+        Facet::Tag | Facet::Year => value.to_string(),
+    }
+}
+
+// e.g. "10-19 Technology & Development"
+fn area_label(value: &str, areas: &[JDArea]) -> String {
+    let Ok(area) = value.parse::<u8>() else {
+        return value.to_string();
+    };
+    match areas.iter().find(|a| a.id == area) {
+        Some(found) => format!("{}-{} {}", area, area.saturating_add(9), found.name),
+        None => format!("{}-{}", area, area.saturating_add(9)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::JDCategory;
+    use super::*;
+
+    struct Post {
+        tags: Vec<String>,
+        published: String,
+    }
+
+    impl Searchable for Post {
+        fn tags(&self) -> &[String] {
+            &self.tags
+        }
+
+        fn category(&self) -> Option<&JDCategory> {
+            None
+        }
+
+        fn published(&self) -> String {
+            self.published.clone()
+        }
+
+        fn has_term(&self, _term: &str) -> bool {
+            false
+        }
+
+        fn contains_phrase(&self, _phrase: &[String]) -> bool {
+            false
+        }
+    }
+
+    fn post(tags: &[&str], published: &str) -> Post {
+        Post { tags: tags.iter().map(|tag| tag.to_string()).collect(), published: published.to_string() }
+    }
+
+    fn counts(values: &[FacetValue]) -> Vec<(&str, usize)> {
+        values.iter().map(|v| (v.value.as_str(), v.count)).collect()
+    }
+
+    #[test]
+    fn nested_tags_count_towards_their_parents() {
+        let posts = vec![
+            post(&["govtech/policy"], "2024-01-01"),
+            post(&["GovTech", "govtech/data/open"], "2024-06-01"),
+            post(&["rust"], "2025-01-01"),
+        ];
+
+        let values = facet_values(&posts, &FacetSelection::default(), Facet::Tag, &[]);
+        assert_eq!(counts(&values), vec![
+            ("govtech", 2),
+            ("govtech/data", 1),
+            ("govtech/data/open", 1),
+            ("govtech/policy", 1),
+            ("rust", 1),
+        ]);
+    }
+
+    #[test]
+    fn counts_match_the_results_of_picking_them() {
+        let posts = vec![
+            post(&["govtech/policy", "rust"], "2024-01-01"),
+            post(&["GovTech", "govtech/data/open"], "2024-06-01"),
+            post(&["govtech/data"], "2025-01-01"),
+            post(&["rust/async"], "2025-03-01"),
+        ];
+        let mut selection = FacetSelection::default();
+        selection.toggle(Facet::Year, "2024");
+
+        for facet in [Facet::Tag, Facet::Year] {
+            for value in facet_values(&posts, &selection, facet, &[]) {
+                let mut picked = selection.clone();
+                picked.selected.insert(facet, vec![value.value.clone()]);
+                let results = posts.iter().filter(|post| picked.matches(*post)).count();
+
+                assert_eq!(value.count, results, "{:?} {}", facet, value.value);
+            }
+        }
+    }
+}
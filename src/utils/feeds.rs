@@ -0,0 +1,355 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use crate::model::{get_all_areas, get_all_categories, Project};
+use crate::utils::markdown::html_escape;
+use crate::utils::site_config::{get_site_config, FeedContent};
+use crate::utils::tags::tag_matches;
+
+// Which posts a feed covers
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedScope {
+    Site,
+    Area(u8),
+    Category(u8),
+    Tag(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+// A feed ready to be written out in any format, with absolute URLs
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub title: String,
+    pub description: String,
+    pub author: String,
+    // The page the feed mirrors
+    pub page_url: String,
+    // The feed itself, for self links
+    pub feed_url: String,
+    pub updated: DateTime<Utc>,
+    pub items: Vec<FeedItem>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedItem {
+    pub title: String,
+    pub url: String,
+    pub summary: String,
+    // Rendered post, when feeds carry full content
+    pub content_html: Option<String>,
+    pub published: DateTime<Utc>,
+    pub tags: Vec<String>,
+    pub jd_identifier: String,
+    pub area_id: Option<u8>,
+    pub area_name: Option<String>,
+    pub category_id: Option<u8>,
+    pub category_name: Option<String>,
+}
+
+impl FeedScope {
+    // Path of the page listing the same posts
+    pub fn page_path(&self) -> String {
+        match self {
+            FeedScope::Site => "/".to_string(),
+            FeedScope::Area(id) => format!("/areas/{}", id),
+            FeedScope::Category(id) => format!("/categories/{}", id),
+            FeedScope::Tag(tag) => format!("/?tag={}", encode_path_segment(tag)),
+        }
+    }
+
+    // Path of the scope's feed, e.g. /areas/10/feed.xml
+    pub fn feed_path(&self, file_name: &str) -> String {
+        match self {
+            FeedScope::Site => format!("/{}", file_name),
+            FeedScope::Area(id) => format!("/areas/{}/{}", id, file_name),
+            FeedScope::Category(id) => format!("/categories/{}/{}", id, file_name),
+            FeedScope::Tag(tag) => format!("/tags/{}/{}", encode_path_segment(tag), file_name),
+        }
+    }
+
+    fn includes(&self, project: &Project) -> bool {
+        match self {
+            FeedScope::Site => true,
+            FeedScope::Area(id) => project.jd_category.as_ref().is_some_and(|c| c.area_id == *id),
+            FeedScope::Category(id) => project.jd_category.as_ref().is_some_and(|c| c.id == *id),
+            FeedScope::Tag(tag) => project.tech_stack.iter().any(|t| tag_matches(t, tag)),
+        }
+    }
+}
+
+impl FeedFormat {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "feed.xml",
+            FeedFormat::Atom => "atom.xml",
+            FeedFormat::Json => "feed.json",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+
+    pub fn render(&self, feed: &Feed) -> String {
+        match self {
+            FeedFormat::Rss => rss_xml(feed),
+            FeedFormat::Atom => atom_xml(feed),
+            FeedFormat::Json => json_feed(feed),
+        }
+    }
+}
+
+// The most recent posts in a scope. Returns `None` for areas and
+// categories that don't exist, and for tags no post has.
+pub fn build_feed(projects: &[Project], scope: &FeedScope, format: FeedFormat) -> Option<Feed> {
+    let config = get_site_config();
+    let site = &config.site;
+
+    let title = match scope {
+        FeedScope::Site => site.title.clone(),
+        FeedScope::Area(id) => {
+            let area = get_all_areas().into_iter().find(|a| a.id == *id)?;
+            format!("{} - {}", area.name, site.title)
+        }
+        FeedScope::Category(id) => {
+            let category = get_all_categories().into_iter().find(|c| c.id == *id)?;
+            format!("{} - {}", category.name, site.title)
+        }
+        FeedScope::Tag(tag) => {
+            if !projects.iter().any(|p| scope.includes(p)) {
+                return None;
+            }
+            format!("#{} - {}", tag, site.title)
+        }
+    };
+
+    let areas = get_all_areas();
+    let mut posts: Vec<&Project> = projects.iter().filter(|p| scope.includes(p)).collect();
+    posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+    posts.truncate(config.feeds.limit);
+
+    let items: Vec<FeedItem> = posts.into_iter()
+        .map(|project| {
+            let url = format!("{}/projects/{}", site.base_url, project.slug);
+            let content_html = match config.feeds.content {
+                FeedContent::Full => project.rendered.as_ref()
+                    .map(|rendered| absolute_urls(&rendered.html, &site.base_url, &url)),
+                FeedContent::Summary => None,
+            };
+
+            FeedItem {
+                title: project.title.clone(),
+                summary: project.summary.clone(),
+                content_html,
+                published: DateTime::<Utc>::from(project.created_at),
+                tags: project.tech_stack.clone(),
+                jd_identifier: project.jd_identifier.clone(),
+                area_id: project.jd_category.as_ref().map(|c| c.area_id),
+                area_name: project.jd_category.as_ref()
+                    .and_then(|c| areas.iter().find(|a| a.id == c.area_id))
+                    .map(|a| a.name.clone()),
+                category_id: project.jd_category.as_ref().map(|c| c.id),
+                category_name: project.jd_category.as_ref().map(|c| c.name.clone()),
+                url,
+            }
+        })
+        .collect();
+
+    // Readers treat a changed `updated` as an edited post, and file times change
+    // on every deploy, so feeds follow publish dates
+    let updated = items.iter().map(|item| item.published).max().unwrap_or_else(Utc::now);
+
+    Some(Feed {
+        title,
+        description: site.description.clone(),
+        author: site.author.clone(),
+        page_url: format!("{}{}", site.base_url, scope.page_path()),
+        feed_url: format!("{}{}", site.base_url, scope.feed_path(format.file_name())),
+        updated,
+        items,
+    })
+}
+
+// Feed readers show posts away from the site, so root-relative links and
+// in-page fragments have to be made absolute. Protocol-relative URLs
+// (`//host/path`) already name their host and are left alone.
+fn absolute_urls(html: &str, base_url: &str, post_url: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(pos) = rest.find("=\"") {
+        let (before, url) = rest.split_at(pos + 2);
+        result.push_str(before);
+        rest = url;
+
+        let attribute = if before.ends_with(" href=\"") {
+            "href"
+        } else if before.ends_with(" src=\"") {
+            "src"
+        } else {
+            continue;
+        };
+
+        if url.starts_with('/') && !url.starts_with("//") {
+            result.push_str(base_url);
+        } else if url.starts_with('#') && attribute == "href" {
+            result.push_str(post_url);
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+// Percent-encode a tag for use as one path segment ("govtech/policy" -> "govtech%2Fpolicy")
+pub fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    encoded
+}
+
+pub fn rss_xml(feed: &Feed) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n");
+    xml.push_str("<channel>\n");
+    xml.push_str(&format!("  <title>{}</title>\n", html_escape(&feed.title)));
+    xml.push_str(&format!("  <link>{}</link>\n", html_escape(&feed.page_url)));
+    xml.push_str(&format!("  <description>{}</description>\n", html_escape(&feed.description)));
+    xml.push_str("  <language>en</language>\n");
+    xml.push_str(&format!("  <lastBuildDate>{}</lastBuildDate>\n", feed.updated.to_rfc2822()));
+    xml.push_str(&format!(
+        "  <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        html_escape(&feed.feed_url)
+    ));
+
+    for item in &feed.items {
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", html_escape(&item.title)));
+        xml.push_str(&format!("    <link>{}</link>\n", html_escape(&item.url)));
+        xml.push_str(&format!("    <guid isPermaLink=\"true\">{}</guid>\n", html_escape(&item.url)));
+        xml.push_str(&format!("    <pubDate>{}</pubDate>\n", item.published.to_rfc2822()));
+        xml.push_str(&format!("    <description>{}</description>\n", html_escape(&item.summary)));
+        if let Some(content) = &item.content_html {
+            xml.push_str(&format!("    <content:encoded>{}</content:encoded>\n", html_escape(content)));
+        }
+        if let Some(category) = &item.category_name {
+            xml.push_str(&format!("    <category>{}</category>\n", html_escape(category)));
+        }
+        for tag in &item.tags {
+            xml.push_str(&format!("    <category>{}</category>\n", html_escape(tag)));
+        }
+        xml.push_str("  </item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+pub fn atom_xml(feed: &Feed) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{}</title>\n", html_escape(&feed.title)));
+    xml.push_str(&format!("  <subtitle>{}</subtitle>\n", html_escape(&feed.description)));
+    xml.push_str(&format!("  <link href=\"{}\"/>\n", html_escape(&feed.page_url)));
+    xml.push_str(&format!("  <link href=\"{}\" rel=\"self\" type=\"application/atom+xml\"/>\n", html_escape(&feed.feed_url)));
+    xml.push_str(&format!("  <id>{}</id>\n", html_escape(&feed.feed_url)));
+    xml.push_str(&format!("  <updated>{}</updated>\n", feed.updated.to_rfc3339()));
+    xml.push_str(&format!("  <author><name>{}</name></author>\n", html_escape(&feed.author)));
+
+    for item in &feed.items {
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", html_escape(&item.title)));
+        xml.push_str(&format!("    <link href=\"{}\"/>\n", html_escape(&item.url)));
+        xml.push_str(&format!("    <id>{}</id>\n", html_escape(&item.url)));
+        xml.push_str(&format!("    <published>{}</published>\n", item.published.to_rfc3339()));
+        xml.push_str(&format!("    <updated>{}</updated>\n", item.published.to_rfc3339()));
+        xml.push_str(&format!("    <summary>{}</summary>\n", html_escape(&item.summary)));
+        if let Some(content) = &item.content_html {
+            xml.push_str(&format!("    <content type=\"html\">{}</content>\n", html_escape(content)));
+        }
+        for tag in &item.tags {
+            xml.push_str(&format!("    <category term=\"{}\"/>\n", html_escape(tag)));
+        }
+        xml.push_str("  </entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+// JSON Feed 1.1 (https://jsonfeed.org/version/1.1). Each item carries its
+// Johnny Decimal placement in a `_jd` extension object.
+pub fn json_feed(feed: &Feed) -> String {
+    let items: Vec<Value> = feed.items.iter()
+        .map(|item| {
+            let mut entry = json!({
+                "id": item.url,
+                "url": item.url,
+                "title": item.title,
+                "summary": item.summary,
+                "date_published": item.published.to_rfc3339(),
+                "tags": item.tags,
+                "_jd": {
+                    "identifier": item.jd_identifier,
+                    "area": item.area_id.map(|id| json!({ "id": id, "name": item.area_name })),
+                    "category": item.category_id.map(|id| json!({ "id": id, "name": item.category_name })),
+                },
+            });
+
+            // Items need content; summary-only feeds use the summary as text
+            match &item.content_html {
+                Some(html) => entry["content_html"] = json!(html),
+                None => entry["content_text"] = json!(item.summary),
+            }
+
+            entry
+        })
+        .collect();
+
+    let feed = json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": feed.title,
+        "home_page_url": feed.page_url,
+        "feed_url": feed.feed_url,
+        "description": feed.description,
+        "language": "en",
+        "authors": [{ "name": feed.author }],
+        "items": items,
+    });
+
+    serde_json::to_string_pretty(&feed).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn makes_root_relative_urls_absolute() {
+        let html = r##"<a href="/posts/a">a</a> <img src="/img/b.png"> <a href="#notes">c</a> <img src="#x">
+            <a href="//cdn.example.com/d">d</a> <img src="//cdn.example.com/e.png"> <a href="https://x.org/">f</a> <span data-href="/g">"##;
+
+        assert_eq!(
+            absolute_urls(html, "https://site.dev", "https://site.dev/posts/this"),
+            r##"<a href="https://site.dev/posts/a">a</a> <img src="https://site.dev/img/b.png"> <a href="https://site.dev/posts/this#notes">c</a> <img src="#x">
+            <a href="//cdn.example.com/d">d</a> <img src="//cdn.example.com/e.png"> <a href="https://x.org/">f</a> <span data-href="/g">"##,
+        );
+    }
+}
@@ -26,3 +26,50 @@ pub fn parse_date_string(date_str: &str) -> Option<SystemTime> {
 
     None
 }
+
+// Shorten `text` to at most `max_chars` characters (not bytes), cutting at the
+// last word boundary and adding an ellipsis when anything was removed
+pub fn truncate_on_word_boundary(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let cut: String = text.chars().take(max_chars).collect();
+    let ends_at_word = text.chars().nth(max_chars).is_some_and(char::is_whitespace);
+
+    // Back up to the last whitespace unless the cut already ends a word, or
+    // the first word is already too long
+    let shortened = match cut.rfind(char::is_whitespace) {
+        Some(pos) if pos > 0 && !ends_at_word => &cut[..pos],
+        _ => cut.as_str(),
+    };
+
+    format!("{}...", shortened.trim_end_matches(|c: char| c.is_whitespace() || ",;:-".contains(c)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_at_word_boundaries() {
+        assert_eq!(truncate_on_word_boundary("  short text  ", 20), "short text");
+        assert_eq!(truncate_on_word_boundary("one two three", 9), "one two...");
+        assert_eq!(truncate_on_word_boundary("one two three", 7), "one two...");
+        assert_eq!(truncate_on_word_boundary("one two, three", 9), "one two...");
+        assert_eq!(truncate_on_word_boundary("unbreakable", 4), "unbr...");
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        // The cut falls inside and right after multi-byte characters
+        assert_eq!(truncate_on_word_boundary("Café déjà vu", 7), "Café...");
+        assert_eq!(truncate_on_word_boundary("Café déjà vu", 9), "Café déjà...");
+        assert_eq!(truncate_on_word_boundary("日本語のテキスト です", 5), "日本語のテ...");
+        assert_eq!(truncate_on_word_boundary("emoji 🦀🦀 crab", 7), "emoji...");
+        assert_eq!(truncate_on_word_boundary("emoji 🦀🦀 crab", 8), "emoji 🦀🦀...");
+        assert_eq!(truncate_on_word_boundary("ñ", 1), "ñ");
+    }
+}
@@ -0,0 +1,253 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::utils::search::{stem, word_spans, STOP_WORDS};
+
+// Most close matches offered for one misspelt word
+const MAX_SIMILAR: usize = 10;
+
+// Edit distance between two words, counting insertions, deletions,
+// substitutions and swaps of adjacent letters as one edit each
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut rows: Vec<Vec<usize>> = (0..=a.len())
+        .map(|i| (0..=b.len()).map(|j| if i == 0 { j } else if j == 0 { i } else { 0 }).collect())
+        .collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+// How many typos a word can have and still match: none for short words,
+// where one edit makes a different word, and more as words get longer
+pub fn max_typos(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+// The words and tags of a set of posts, for completing and correcting queries
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Vocabulary {
+    // Every stemmed term, sorted, and how many posts contain it
+    pub terms: Vec<String>,
+    pub counts: Vec<u32>,
+    // The most common spelling of each term, where it differs from the stem
+    pub spellings: BTreeMap<String, String>,
+    pub tags: Vec<String>,
+}
+
+impl Vocabulary {
+    pub fn contains(&self, term: &str) -> bool {
+        self.position(term).is_some()
+    }
+
+    fn position(&self, term: &str) -> Option<usize> {
+        self.terms.binary_search_by(|t| t.as_str().cmp(term)).ok()
+    }
+
+    fn count(&self, term: &str) -> u32 {
+        self.position(term).map_or(0, |i| self.counts[i])
+    }
+
+    // How a term is usually written, e.g. "general" for "gener"
+    pub fn spelling<'a>(&'a self, term: &'a str) -> &'a str {
+        self.spellings.get(term).map_or(term, String::as_str)
+    }
+
+    // Terms that a partly typed word could become, by stem or usual spelling
+    pub fn completions(&self, prefix: &str) -> Vec<String> {
+        let start = self.terms.partition_point(|t| t.as_str() < prefix);
+        let mut completions: Vec<String> = self.terms[start..].iter()
+            .take_while(|t| t.starts_with(prefix))
+            .cloned()
+            .collect();
+
+        for (term, spelling) in &self.spellings {
+            if spelling.starts_with(prefix) && !completions.contains(term) {
+                completions.push(term.clone());
+            }
+        }
+
+        completions
+    }
+
+    // Terms within a few typos of one the vocabulary doesn't contain,
+    // closest and most common first. Numbers are never corrected.
+    pub fn similar(&self, term: &str) -> Vec<String> {
+        let typos = max_typos(term);
+        if typos == 0 || term.chars().any(|c| c.is_numeric()) {
+            return Vec::new();
+        }
+
+        let length = term.chars().count();
+        let mut similar: Vec<(usize, u32, &String)> = self.terms.iter()
+            .filter(|t| t.chars().count().abs_diff(length) <= typos)
+            .map(|t| (edit_distance(term, t), self.count(t), t))
+            .filter(|(distance, _, _)| *distance <= typos)
+            .collect();
+
+        similar.sort_by_key(|(distance, count, t)| (*distance, Reverse(*count), t.as_str()));
+        similar.into_iter().take(MAX_SIMILAR).map(|(_, _, t)| t.clone()).collect()
+    }
+
+    // Tags within a few typos of one no post has, closest first
+    pub fn similar_tags(&self, tag: &str) -> Vec<String> {
+        let tag = tag.to_lowercase();
+        let typos = max_typos(&tag);
+
+        let mut similar: Vec<(usize, &String)> = self.tags.iter()
+            .map(|t| (edit_distance(&tag, &t.to_lowercase()), t))
+            .filter(|(distance, _)| *distance <= typos)
+            .collect();
+
+        similar.sort();
+        similar.into_iter().take(MAX_SIMILAR).map(|(_, t)| t.clone()).collect()
+    }
+}
+
+// Collects a vocabulary one post at a time
+#[derive(Debug, Default)]
+pub struct VocabularyBuilder {
+    counts: HashMap<String, u32>,
+    spellings: HashMap<String, HashMap<String, u32>>,
+    tags: Vec<String>,
+}
+
+impl VocabularyBuilder {
+    pub fn add_post(&mut self, text: &str, tags: &[String]) {
+        let mut seen = HashSet::new();
+
+        for (_, _, word) in word_spans(text) {
+            if STOP_WORDS.contains(&word.as_str()) {
+                continue;
+            }
+            let term = stem(&word);
+
+            if seen.insert(term.clone()) {
+                *self.counts.entry(term.clone()).or_default() += 1;
+            }
+            *self.spellings.entry(term).or_default().entry(word).or_default() += 1;
+        }
+
+        for tag in tags {
+            if !self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                self.tags.push(tag.clone());
+            }
+        }
+    }
+
+    pub fn build(self) -> Vocabulary {
+        let mut terms: Vec<(String, u32)> = self.counts.into_iter().collect();
+        terms.sort();
+
+        let spellings = self.spellings.into_iter()
+            .filter_map(|(term, words)| {
+                let (word, _) = words.into_iter().max_by_key(|(word, count)| (*count, Reverse(word.clone())))?;
+                (word != term).then_some((term, word))
+            })
+            .collect();
+
+        let mut tags = self.tags;
+        tags.sort_by_key(|tag| tag.to_lowercase());
+
+        Vocabulary {
+            terms: terms.iter().map(|(term, _)| term.clone()).collect(),
+            counts: terms.iter().map(|(_, count)| *count).collect(),
+            spellings,
+            tags,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocabulary(terms: &[(&str, u32)], spellings: &[(&str, &str)]) -> Vocabulary {
+        Vocabulary {
+            terms: terms.iter().map(|(term, _)| term.to_string()).collect(),
+            counts: terms.iter().map(|(_, count)| *count).collect(),
+            spellings: spellings.iter().map(|(term, word)| (term.to_string(), word.to_string())).collect(),
+            tags: vec!["Rust".to_string(), "govtech".to_string()],
+        }
+    }
+
+    #[test]
+    fn edit_distance_counts_swaps_as_one_edit() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("rust", "rust"), 0);
+        assert_eq!(edit_distance("rust", "rsut"), 1);
+        assert_eq!(edit_distance("rust", "rest"), 1);
+        assert_eq!(edit_distance("rust", "rusty"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn similar_respects_the_typo_allowance() {
+        let vocab = vocabulary(&[("async", 3), ("macro", 2), ("parallel", 1), ("rust", 5)], &[]);
+
+        // Words of three letters or fewer are never corrected
+        assert!(vocab.similar("rst").is_empty());
+        assert!(vocab.similar("").is_empty());
+        // One typo from 4 letters, two from 8
+        assert_eq!(vocab.similar("rsut"), ["rust"]);
+        assert_eq!(vocab.similar("macor"), ["macro"]);
+        assert!(vocab.similar("mcaor").is_empty());
+        assert_eq!(vocab.similar("paralelx"), ["parallel"]);
+        assert!(vocab.similar("paralex").is_empty());
+        // Numbers are left alone
+        assert!(vocab.similar("macr0").is_empty());
+    }
+
+    #[test]
+    fn similar_ranks_by_distance_then_count() {
+        let vocab = vocabulary(&[("pars", 9), ("parse", 1), ("parsec", 4), ("parsed", 9), ("parser", 4), ("party", 7)], &[]);
+
+        // "pars" and "party" are two edits away; equally close and common
+        // terms are in alphabetical order
+        assert_eq!(vocab.similar("parsex"), ["parsed", "parsec", "parser", "parse"]);
+        // An exact match comes first
+        assert_eq!(vocab.similar("pars"), ["pars", "parse"]);
+    }
+
+    #[test]
+    fn completions_match_stems_and_spellings() {
+        let vocab = vocabulary(&[("gener", 2), ("generic", 1), ("macro", 1)], &[("gener", "general")]);
+
+        assert_eq!(vocab.completions("gen"), ["gener", "generic"]);
+        assert_eq!(vocab.completions("genera"), ["gener"]);
+        assert_eq!(vocab.completions(""), ["gener", "generic", "macro"]);
+        assert!(vocab.completions("x").is_empty());
+    }
+
+    #[test]
+    fn similar_tags_ignore_case() {
+        let vocab = vocabulary(&[], &[]);
+
+        assert_eq!(vocab.similar_tags("govtehc"), ["govtech"]);
+        assert_eq!(vocab.similar_tags("RUST"), ["Rust"]);
+        assert_eq!(vocab.similar_tags("rsut"), ["Rust"]);
+        assert!(vocab.similar_tags("rst").is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use regex::Regex;
+use leptos::logging::log;
+use crate::model::GlossaryEntry;
+use crate::utils::jd_content_loader::slug_from_title;
+
+// Glossary terms plus a matcher that finds them in post text
+#[derive(Debug, Clone)]
+pub struct Glossary {
+    pub entries: Vec<GlossaryEntry>,
+    matcher: Option<Regex>,
+    // Lowercased term or alias -> index into `entries`
+    lookup: HashMap<String, usize>,
+}
+
+impl Glossary {
+    pub fn new(mut entries: Vec<GlossaryEntry>) -> Self {
+        entries.sort_by_key(|e| e.term.to_lowercase());
+
+        let mut lookup = HashMap::new();
+        let mut names: Vec<&str> = Vec::new();
+
+        for (i, entry) in entries.iter().enumerate() {
+            for name in std::iter::once(&entry.term).chain(&entry.aliases) {
+                if !name.is_empty() && lookup.insert(name.to_lowercase(), i).is_none() {
+                    names.push(name);
+                }
+            }
+        }
+
+        // Longest names first so "Authority to Operate" wins over "Authority"
+        names.sort_by_key(|n| std::cmp::Reverse(n.len()));
+
+        let matcher = if names.is_empty() {
+            None
+        } else {
+            let pattern = names.iter().map(|n| term_pattern(n)).collect::<Vec<_>>().join("|");
+            Regex::new(&pattern).ok()
+        };
+
+        Glossary { entries, matcher, lookup }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Glossary terms in a run of text, as (start, end, entry) byte ranges
+    pub fn find_terms(&self, text: &str) -> Vec<(usize, usize, &GlossaryEntry)> {
+        let Some(matcher) = &self.matcher else {
+            return Vec::new();
+        };
+
+        matcher.find_iter(text)
+            .filter_map(|m| {
+                let index = self.lookup.get(&m.as_str().to_lowercase())?;
+                Some((m.start(), m.end(), &self.entries[*index]))
+            })
+            .collect()
+    }
+}
+
+// Abbreviations (all caps, like "ATO") only match exactly so they don't
+// catch ordinary words; other terms match in any case
+fn term_pattern(name: &str) -> String {
+    let is_abbreviation = name.chars().any(|c| c.is_alphabetic())
+        && !name.chars().any(|c| c.is_lowercase());
+    let case = if is_abbreviation { "" } else { "(?i)" };
+
+    let starts_word = name.chars().next().is_some_and(|c| c.is_alphanumeric());
+    let ends_word = name.chars().last().is_some_and(|c| c.is_alphanumeric());
+
+    format!(
+        "(?:{}{}{}{})",
+        case,
+        if starts_word { r"\b" } else { "" },
+        regex::escape(name),
+        if ends_word { r"\b" } else { "" }
+    )
+}
+
+// Load glossary terms from `<root>/glossary.md` and `<root>/glossary/*.md`
+pub fn load_glossary(root_dir: &str) -> Vec<GlossaryEntry> {
+    let root = Path::new(root_dir);
+    let mut entries = Vec::new();
+
+    if let Ok(content) = fs::read_to_string(root.join("glossary.md")) {
+        entries.extend(parse_glossary_file(&content));
+    }
+
+    if let Ok(dir) = fs::read_dir(root.join("glossary")) {
+        for entry in dir.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "md") {
+                if let Ok(content) = fs::read_to_string(&path) {
+                    entries.extend(parse_term_file(&path, &content));
+                }
+            }
+        }
+    }
+
+    log!("Loaded {} glossary terms from {}", entries.len(), root_dir);
+    entries
+}
+
+// glossary.md: each `## Term` heading starts an entry. An optional
+// `aliases: A, B` line right after it lists other spellings, and the rest
+// of the section is the definition.
+fn parse_glossary_file(content: &str) -> Vec<GlossaryEntry> {
+    let mut entries = Vec::new();
+    let mut current: Option<(String, Vec<String>, Vec<&str>)> = None;
+
+    for line in content.lines() {
+        if let Some(term) = line.strip_prefix("## ") {
+            if let Some((term, aliases, body)) = current.take() {
+                entries.push(make_entry(term, aliases, &body.join("\n")));
+            }
+            current = Some((term.trim().to_string(), Vec::new(), Vec::new()));
+            continue;
+        }
+
+        let Some((_, aliases, body)) = current.as_mut() else {
+            continue;
+        };
+
+        let is_first_line = body.iter().all(|l| l.trim().is_empty());
+        match alias_line(line) {
+            Some(list) if is_first_line && aliases.is_empty() => *aliases = list,
+            _ => body.push(line),
+        }
+    }
+
+    if let Some((term, aliases, body)) = current {
+        entries.push(make_entry(term, aliases, &body.join("\n")));
+    }
+
+    entries
+}
+
+// glossary/*.md: one term per file, with `term` and `aliases` in the front
+// matter (the term falls back to the first heading or the file name)
+fn parse_term_file(path: &Path, content: &str) -> Option<GlossaryEntry> {
+    let mut term = String::new();
+    let mut aliases = Vec::new();
+    let mut body = content.trim_start();
+
+    if let Some(rest) = body.strip_prefix("---") {
+        if let Some((front_matter, main)) = rest.split_once("\n---") {
+            for line in front_matter.lines() {
+                if let Some(list) = alias_line(line) {
+                    aliases = list;
+                } else if let Some(value) = line.trim().strip_prefix("term:") {
+                    term = unquote(value.trim()).to_string();
+                }
+            }
+            body = main;
+        }
+    }
+
+    let mut definition: Vec<&str> = body.lines().collect();
+
+    if term.is_empty() {
+        if let Some(pos) = definition.iter().position(|l| l.starts_with("# ")) {
+            term = definition.remove(pos)[2..].trim().to_string();
+        } else {
+            term = path.file_stem()?.to_string_lossy().to_string();
+        }
+    }
+
+    Some(make_entry(term, aliases, &definition.join("\n")))
+}
+
+fn alias_line(line: &str) -> Option<Vec<String>> {
+    let line = line.trim();
+    let (key, value) = line.split_once(':')?;
+
+    if !key.trim().eq_ignore_ascii_case("aliases") {
+        return None;
+    }
+
+    let value = value.trim().trim_start_matches('[').trim_end_matches(']');
+    Some(value.split(',')
+        .map(|s| unquote(s.trim()).to_string())
+        .filter(|s| !s.is_empty())
+        .collect())
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches(|c| c == '"' || c == '\'')
+}
+
+fn make_entry(term: String, aliases: Vec<String>, definition: &str) -> GlossaryEntry {
+    GlossaryEntry {
+        slug: slug_from_title(&term),
+        term,
+        aliases,
+        definition: definition.trim().to_string(),
+    }
+}
+
+// The site glossary, loaded on first use. The compiled matcher is built
+// once and shared by every render.
+pub fn get_glossary() -> &'static Glossary {
+    static GLOSSARY: OnceLock<Glossary> = OnceLock::new();
+
+    GLOSSARY.get_or_init(|| {
+        let content_root = option_env!("GLOSSARY_DIR").unwrap_or("content");
+        Glossary::new(load_glossary(content_root))
+    })
+}
@@ -0,0 +1,334 @@
+use crate::utils::markdown::html_escape;
+
+// Enough of a language's syntax to colour it: keywords, literals, comments,
+// strings and numbers. Anything else is left as plain text.
+struct Language {
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    // `'` starts a char literal only when it closes right away (`'a'`, `'\n'`),
+    // so Rust lifetimes aren't read as strings
+    char_literals: bool,
+    triple_quotes: bool,
+    case_insensitive: bool,
+}
+
+const C_LIKE_COMMENT: Option<(&str, &str)> = Some(("/*", "*/"));
+
+const RUST: Language = Language {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while",
+    ],
+    literals: &["true", "false", "None", "Some", "Ok", "Err"],
+    line_comments: &["//"],
+    block_comment: C_LIKE_COMMENT,
+    quotes: &['"'],
+    char_literals: true,
+    triple_quotes: false,
+    case_insensitive: false,
+};
+
+const PYTHON: Language = Language {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+    ],
+    literals: &["True", "False", "None"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    char_literals: false,
+    triple_quotes: true,
+    case_insensitive: false,
+};
+
+const JAVASCRIPT: Language = Language {
+    keywords: &[
+        "async", "await", "break", "case", "catch", "class", "const", "continue", "default",
+        "delete", "do", "else", "export", "extends", "finally", "for", "from", "function", "if",
+        "import", "in", "instanceof", "interface", "let", "new", "of", "return", "static", "switch",
+        "this", "throw", "try", "type", "typeof", "var", "void", "while", "yield",
+    ],
+    literals: &["true", "false", "null", "undefined"],
+    line_comments: &["//"],
+    block_comment: C_LIKE_COMMENT,
+    quotes: &['"', '\'', '`'],
+    char_literals: false,
+    triple_quotes: false,
+    case_insensitive: false,
+};
+
+const GO: Language = Language {
+    keywords: &[
+        "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough",
+        "for", "func", "go", "goto", "if", "import", "interface", "map", "package", "range",
+        "return", "select", "struct", "switch", "type", "var",
+    ],
+    literals: &["true", "false", "nil", "iota"],
+    line_comments: &["//"],
+    block_comment: C_LIKE_COMMENT,
+    quotes: &['"', '`'],
+    char_literals: true,
+    triple_quotes: false,
+    case_insensitive: false,
+};
+
+const C: Language = Language {
+    keywords: &[
+        "auto", "break", "case", "catch", "char", "class", "const", "continue", "default",
+        "delete", "do", "double", "else", "enum", "extern", "float", "for", "goto", "if",
+        "include", "int", "long", "namespace", "new", "private", "protected", "public", "return",
+        "short", "signed", "sizeof", "static", "struct", "switch", "template", "this", "throw",
+        "try", "typedef", "union", "unsigned", "using", "virtual", "void", "volatile", "while",
+    ],
+    literals: &["true", "false", "NULL", "nullptr"],
+    line_comments: &["//"],
+    block_comment: C_LIKE_COMMENT,
+    quotes: &['"'],
+    char_literals: true,
+    triple_quotes: false,
+    case_insensitive: false,
+};
+
+const JAVA: Language = Language {
+    keywords: &[
+        "abstract", "break", "case", "catch", "class", "continue", "default", "do", "else",
+        "enum", "extends", "final", "finally", "for", "if", "implements", "import", "instanceof",
+        "interface", "new", "package", "private", "protected", "public", "return", "static",
+        "super", "switch", "this", "throw", "throws", "try", "var", "void", "while",
+    ],
+    literals: &["true", "false", "null"],
+    line_comments: &["//"],
+    block_comment: C_LIKE_COMMENT,
+    quotes: &['"'],
+    char_literals: true,
+    triple_quotes: false,
+    case_insensitive: false,
+};
+
+const SHELL: Language = Language {
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "until", "while",
+    ],
+    literals: &["true", "false"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    char_literals: false,
+    triple_quotes: false,
+    case_insensitive: false,
+};
+
+const SQL: Language = Language {
+    keywords: &[
+        "and", "as", "asc", "by", "create", "delete", "desc", "distinct", "drop", "from", "group",
+        "having", "in", "index", "insert", "into", "is", "join", "left", "limit", "not", "on",
+        "or", "order", "right", "select", "set", "table", "union", "update", "values", "where", "with",
+    ],
+    literals: &["true", "false", "null"],
+    line_comments: &["--"],
+    block_comment: C_LIKE_COMMENT,
+    quotes: &['\''],
+    char_literals: false,
+    triple_quotes: false,
+    case_insensitive: true,
+};
+
+// JSON, TOML and YAML only have literals, strings, numbers and comments
+const DATA: Language = Language {
+    keywords: &[],
+    literals: &["true", "false", "null"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    char_literals: false,
+    triple_quotes: false,
+    case_insensitive: false,
+};
+
+fn language(name: &str) -> Option<&'static Language> {
+    match name.to_ascii_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "python" | "py" | "python3" => Some(&PYTHON),
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => Some(&JAVASCRIPT),
+        "go" | "golang" => Some(&GO),
+        "c" | "h" | "cpp" | "c++" | "cc" | "hpp" => Some(&C),
+        "java" | "kotlin" => Some(&JAVA),
+        "bash" | "sh" | "shell" | "zsh" => Some(&SHELL),
+        "sql" => Some(&SQL),
+        "json" | "toml" | "yaml" | "yml" => Some(&DATA),
+        _ => None,
+    }
+}
+
+// Highlight `code` as HTML with `<span class="hl-...">` around keywords,
+// literals, strings, numbers and comments. `None` for languages we don't know.
+pub fn highlight_code(code: &str, language_name: &str) -> Option<String> {
+    let lang = language(language_name)?;
+    let mut html = String::with_capacity(code.len() * 2);
+    let mut pos = 0;
+
+    while pos < code.len() {
+        let rest = &code[pos..];
+        let c = rest.chars().next().unwrap_or_default();
+        let previous = code[..pos].chars().next_back();
+        let at_word_start = previous.map_or(true, |p| !is_word_char(p));
+
+        let (class, len) = if let Some(len) = comment_len(rest, lang, previous.map_or(true, char::is_whitespace)) {
+            (Some("hl-comment"), len)
+        } else if let Some(len) = string_len(rest, lang) {
+            (Some("hl-string"), len)
+        } else if c.is_ascii_digit() && at_word_start {
+            (Some("hl-number"), number_len(rest))
+        } else if is_word_char(c) && at_word_start {
+            let len = rest.find(|next: char| !is_word_char(next)).unwrap_or(rest.len());
+            (word_class(&rest[..len], lang), len)
+        } else {
+            (None, c.len_utf8())
+        };
+
+        let text = html_escape(&rest[..len]);
+        match class {
+            Some(class) => html.push_str(&format!("<span class=\"{}\">{}</span>", class, text)),
+            None => html.push_str(&text),
+        }
+        pos += len;
+    }
+
+    Some(html)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn comment_len(rest: &str, lang: &Language, after_space: bool) -> Option<usize> {
+    if let Some((open, close)) = lang.block_comment {
+        if let Some(body) = rest.strip_prefix(open) {
+            return Some(body.find(close).map_or(rest.len(), |end| open.len() + end + close.len()));
+        }
+    }
+
+    // `#` only starts a comment after whitespace (`$#` is a variable in shell)
+    lang.line_comments.iter()
+        .any(|marker| rest.starts_with(marker) && (after_space || *marker != "#"))
+        .then(|| rest.find('\n').unwrap_or(rest.len()))
+}
+
+fn string_len(rest: &str, lang: &Language) -> Option<usize> {
+    let quote = rest.chars().next()?;
+
+    if lang.char_literals && quote == '\'' {
+        // 'a', '\n' or '\u{1F980}', but not a lifetime like 'a
+        let close = rest[1..].find('\'')? + 1;
+        let inner = &rest[1..close];
+        let is_char = inner.chars().count() == 1 || (inner.starts_with('\\') && !inner.contains(char::is_whitespace));
+        return is_char.then_some(close + 1);
+    }
+
+    if !lang.quotes.contains(&quote) {
+        return None;
+    }
+
+    if lang.triple_quotes {
+        let triple = quote.to_string().repeat(3);
+        if let Some(body) = rest.strip_prefix(&triple) {
+            return Some(body.find(&triple).map_or(rest.len(), |end| 3 + end + 3));
+        }
+    }
+
+    // To the closing quote, skipping escaped characters. Only template
+    // literals and Go raw strings run over several lines.
+    let multiline = quote == '`';
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '\n' if !multiline => return Some(i),
+            c if c == quote => return Some(i + 1),
+            _ => {}
+        }
+    }
+
+    Some(rest.len())
+}
+
+fn number_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut len = 0;
+
+    while len < bytes.len() {
+        let b = bytes[len];
+        // A `.` only continues the number if a digit follows, so `0..10` stays a range
+        let decimal_point = b == b'.' && bytes.get(len + 1).is_some_and(u8::is_ascii_digit);
+        if !(b.is_ascii_alphanumeric() || b == b'_' || decimal_point) {
+            break;
+        }
+        len += 1;
+    }
+
+    len
+}
+
+fn word_class(word: &str, lang: &Language) -> Option<&'static str> {
+    let matches = |list: &[&str]| if lang.case_insensitive {
+        list.iter().any(|k| k.eq_ignore_ascii_case(word))
+    } else {
+        list.contains(&word)
+    };
+
+    if matches(lang.keywords) {
+        Some("hl-keyword")
+    } else if matches(lang.literals) {
+        Some("hl-literal")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_rust() {
+        let html = highlight_code("fn main<'a>() { let c = '\\n'; // done\n    println!(\"<{}>\", 1.5); }", "rust").unwrap();
+
+        assert_eq!(html, "<span class=\"hl-keyword\">fn</span> main&lt;&#39;a&gt;() { \
+            <span class=\"hl-keyword\">let</span> c = <span class=\"hl-string\">&#39;\\n&#39;</span>; \
+            <span class=\"hl-comment\">// done</span>\n    \
+            println!(<span class=\"hl-string\">&quot;&lt;{}&gt;&quot;</span>, <span class=\"hl-number\">1.5</span>); }");
+    }
+
+    #[test]
+    fn highlights_python_and_sql() {
+        let html = highlight_code("def f(x):  # note\n    return \"\"\"a\n\"b\"\"\" if x else None", "python").unwrap();
+        assert!(html.starts_with("<span class=\"hl-keyword\">def</span> f(x):  <span class=\"hl-comment\"># note</span>\n"), "{}", html);
+        assert!(html.contains("<span class=\"hl-string\">&quot;&quot;&quot;a\n&quot;b&quot;&quot;&quot;</span>"), "{}", html);
+        assert!(html.ends_with("<span class=\"hl-literal\">None</span>"), "{}", html);
+
+        let html = highlight_code("SELECT name FROM t -- all", "sql").unwrap();
+        assert_eq!(html, "<span class=\"hl-keyword\">SELECT</span> name <span class=\"hl-keyword\">FROM</span> t <span class=\"hl-comment\">-- all</span>");
+    }
+
+    #[test]
+    fn words_inside_identifiers_are_plain() {
+        assert_eq!(highlight_code("format x2 0..10 echo $#", "bash").unwrap(),
+            "format x2 <span class=\"hl-number\">0</span>..<span class=\"hl-number\">10</span> echo $#");
+    }
+
+    #[test]
+    fn unknown_languages_are_left_alone() {
+        assert_eq!(highlight_code("anything", "brainfuck"), None);
+        assert_eq!(highlight_code("", "rust").as_deref(), Some(""));
+    }
+}
@@ -2,16 +2,31 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use crate::model::{Project, JDArea, JDCategory};
+use crate::utils::citations::find_unknown_citations;
+use crate::utils::client_index::client_index;
+use crate::utils::markdown::extract_summary;
+use crate::utils::notebook::notebook_to_markdown;
+use crate::utils::org::org_to_markdown;
+use crate::utils::render_cache::render_projects;
+use crate::utils::search::search_index;
+use crate::utils::shortcodes::find_unknown_shortcodes;
+use crate::utils::tags::{extract_inline_tags, merge_tags};
+
+// Maximum length of generated summaries, in characters
+const SUMMARY_LENGTH: usize = 150;
 
 pub struct FrontMatter {
     pub title: String,
     pub date: Option<String>,
+    pub updated: Option<String>,
     pub tags: Vec<String>,
     pub draft: bool,
     pub area_id: Option<u8>,
     pub category_id: Option<u8>,
     pub summary: Option<String>,
-    pub related_articles: Vec<String>
+    pub thumbnail: Option<String>,
+    pub related_articles: Vec<String>,
+    pub allow_html: bool,
 }
 
 // Extract Johnny Decimal ID from path or filename
@@ -139,8 +154,31 @@ fn process_directory(dir: &Path, results: &mut Vec<(PathBuf, FrontMatter, String
 
             if path.is_dir() {
                 process_directory(&path, results);
-            } else if path.is_file() && path.extension().map_or(false, |ext| ext == "md") {
-                if let Ok(content) = fs::read_to_string(&path) {
+            } else if path.is_file() && path.extension().map_or(false, |ext| ext == "md" || ext == "ipynb" || ext == "org") {
+                if let Ok(mut content) = fs::read_to_string(&path) {
+                    // Notebooks and Org files are converted to markdown and loaded like any other post
+                    match path.extension().and_then(|ext| ext.to_str()) {
+                        Some("ipynb") => match notebook_to_markdown(&content) {
+                            Ok(markdown) => content = markdown,
+                            Err(e) => {
+                                println!("Skipping notebook {}: {}", path.display(), e);
+                                continue;
+                            }
+                        },
+                        Some("org") => content = org_to_markdown(&content),
+                        _ => {}
+                    }
+
+                    // Report shortcodes that have no registered handler
+                    for (line, name) in find_unknown_shortcodes(&content) {
+                        println!("Unknown shortcode `{}` in {} (line {})", name, path.display(), line);
+                    }
+
+                    // Report citations that aren't in the site bibliography
+                    for (line, key) in find_unknown_citations(&content) {
+                        println!("Unknown citation key `{}` in {} (line {})", key, path.display(), line);
+                    }
+
                     let (front_matter, processed_content) = match parse_front_matter(&content) {
                         Some((fm, content)) => (fm, content),
                         None => {
@@ -151,12 +189,15 @@ fn process_directory(dir: &Path, results: &mut Vec<(PathBuf, FrontMatter, String
                             let fm = FrontMatter {
                                 title,
                                 date: None,
+                                updated: None,
                                 tags: Vec::new(),
                                 draft: false,
                                 area_id,
                                 category_id,
                                 summary: Some(first_para),
+                                thumbnail: None,
                                 related_articles: Vec::new(),
+                                allow_html: false,
                             };
                             (fm, content)
                         }
@@ -173,31 +214,16 @@ fn process_directory(dir: &Path, results: &mut Vec<(PathBuf, FrontMatter, String
 // Extract title and first paragraph for summary
 fn extract_title_and_summary(content: &str) -> (String, String) {
     let mut title = String::from("Untitled");
-    let mut first_para = String::new();
 
     // Find the title (first H1)
     for line in content.lines() {
-        if line.starts_with("# ") {
-            title = line[2..].trim().to_string();
+        if let Some(heading) = line.strip_prefix("# ") {
+            title = heading.trim().to_string();
             break;
         }
     }
 
-    // Find the first paragraph that's not a heading
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if !trimmed.is_empty() && !trimmed.starts_with('#') {
-            first_para = trimmed.to_string();
-            break;
-        }
-    }
-
-    // Limit summary length
-    if first_para.len() > 150 {
-        first_para = first_para[..150].to_string() + "...";
-    }
-
-    (title, first_para)
+    (title, extract_summary(content, SUMMARY_LENGTH))
 }
 
 // Parse front matter from markdown content
@@ -227,12 +253,15 @@ fn parse_front_matter(content: &str) -> Option<(FrontMatter, String)> {
     // Parse front matter
     let mut title = String::new();
     let mut date = None;
+    let mut updated = None;
     let mut tags = Vec::new();
     let mut draft = false;
     let mut area_id = None;
     let mut category_id = None;
     let mut summary = None;
+    let mut thumbnail = None;
     let mut related_articles = Vec::new();
+    let mut allow_html = false;
 
     // Parse YAML or TOML front matter
     for line in front_matter_str.lines() {
@@ -255,7 +284,10 @@ fn parse_front_matter(content: &str) -> Option<(FrontMatter, String)> {
             match key {
                 "title" => title = value.to_string(),
                 "date" => date = Some(value.to_string()),
+                "thumbnail" if !value.is_empty() => thumbnail = Some(value.to_string()),
+                "updated" => updated = Some(value.to_string()),
                 "draft" => draft = value.to_lowercase() == "true",
+                "allow_html" => allow_html = value.to_lowercase() == "true",
                 "area_id" => area_id = value.parse::<u8>().ok(),
                 "category_id" => category_id = value.parse::<u8>().ok(),
                 "summary" => {
@@ -321,12 +353,15 @@ fn parse_front_matter(content: &str) -> Option<(FrontMatter, String)> {
     let front_matter = FrontMatter {
         title,
         date,
+        updated,
         tags,
         draft,
         area_id,
         category_id,
         summary,
-        related_articles
+        thumbnail,
+        related_articles,
+        allow_html,
     };
 
     // Debug the completed front matter
@@ -339,7 +374,7 @@ fn parse_front_matter(content: &str) -> Option<(FrontMatter, String)> {
 pub fn markdown_to_projects(root_dir: &str, areas: &[JDArea], categories: &[JDCategory]) -> Vec<Project> {
     let md_files = load_markdown_files(root_dir);
 
-    md_files
+    let mut projects: Vec<Project> = md_files
         .into_iter()
         .filter(|(_, front_matter, _)| !front_matter.draft) // Skip drafts
         .map(|(path, front_matter, content)| {
@@ -385,6 +420,13 @@ pub fn markdown_to_projects(root_dir: &str, areas: &[JDArea], categories: &[JDCa
                 .and_then(|date_str| crate::utils::format::parse_date_string(date_str))
                 .unwrap_or_else(|| SystemTime::now());
 
+            // Last edit: `updated` in the front matter, else the file's modification time
+            let updated_at = front_matter.updated
+                .as_ref()
+                .and_then(|date_str| crate::utils::format::parse_date_string(date_str))
+                .or_else(|| fs::metadata(&path).and_then(|m| m.modified()).ok())
+                .map_or(created_at, |updated| updated.max(created_at));
+
             // Find category based on the extracted JD info
             let category = if let Some(cat_id) = front_matter.category_id {
                 // First priority: Use category_id from front matter
@@ -416,10 +458,19 @@ pub fn markdown_to_projects(root_dir: &str, areas: &[JDArea], categories: &[JDCa
             // Extract summary from front matter or first paragraph
             let summary = front_matter.summary
                 .filter(|s| !s.is_empty())
-                .unwrap_or_else(|| {
-                    let (_, first_para) = extract_title_and_summary(&content);
-                    first_para
-                });
+                .unwrap_or_else(|| extract_summary(&content, SUMMARY_LENGTH));
+
+            // A missing thumbnail would show as a broken image on the page and in link previews
+            let thumbnail = front_matter.thumbnail.filter(|url| {
+                let found = public_asset_exists(url);
+                if !found {
+                    println!("Thumbnail {} not found for {}", url, path.display());
+                }
+                found
+            });
+
+            // Front matter tags plus any inline #tags in the body
+            let tech_stack = merge_tags(front_matter.tags, extract_inline_tags(&content));
 
             Project {
                 id: None, // Will be assigned later
@@ -427,23 +478,46 @@ pub fn markdown_to_projects(root_dir: &str, areas: &[JDArea], categories: &[JDCa
                 slug,
                 summary,
                 content,
-                tech_stack: front_matter.tags,
+                tech_stack,
                 repo_url: None,
                 live_url: None,
-                thumbnail: None,
+                thumbnail,
                 created_at,
-                updated_at: SystemTime::now(),
+                updated_at,
                 jd_category: category,
                 original_path: path.to_string_lossy().to_string(),
                 jd_identifier,
-                related_articles: front_matter.related_articles
+                related_articles: front_matter.related_articles,
+                allow_html: front_matter.allow_html,
+                rendered: None,
             }
         })
-        .collect()
+        .collect();
+
+    // Render each post's markdown once, now that every post is known
+    render_projects(&mut projects);
+
+    // And index it for search, on the server and in the browser. Both only
+    // rebuild when the indexed text changed.
+    search_index(&projects);
+    client_index(&projects);
+
+    projects
+}
+
+// Whether a root-relative URL like `/images/a.png` names a file in `public/`
+// (or the built site root). Other URLs are assumed to be fine.
+fn public_asset_exists(url: &str) -> bool {
+    let Some(relative) = url.strip_prefix('/') else {
+        return true;
+    };
+    let site_root = std::env::var("LEPTOS_SITE_ROOT").unwrap_or_else(|_| "target/site".to_string());
+
+    ["public", site_root.as_str()].iter().any(|root| Path::new(root).join(relative).is_file())
 }
 
 // Helper to generate slug from title
-fn slug_from_title(title: &str) -> String {
+pub fn slug_from_title(title: &str) -> String {
     let mut slug = String::new();
 
     for c in title.chars() {
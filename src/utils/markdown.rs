@@ -1,325 +1,351 @@
-use pulldown_cmark::{Parser, Options, html};
+use std::sync::OnceLock;
+use pulldown_cmark::{Event, Parser, Options, Tag, html};
+use regex::Regex;
+use crate::model::RenderedContent;
+use crate::utils::format::truncate_on_word_boundary;
+use crate::utils::sanitize::{sanitize_html, sanitize_notebook_html};
+use crate::utils::shortcodes::{find_shortcode, join_shortcode_text};
+use crate::utils::site_config::get_site_config;
+use crate::utils::transforms::{trusted_placeholder, RenderContext, TransformPipeline};
+
+
+// Render markdown and sanitise the result with the site's allowlist
+pub fn markdown_to_html(content: &str) -> String {
+    markdown_to_html_with_options(content, false, &mut RenderContext::new(&[]))
+}
 
+// `allow_html` is the per-post escape hatch (`allow_html: true` in front matter).
+// It only skips sanitising when the site config permits post overrides.
+// Transforms can look up other posts through `ctx` and report problems to it.
+pub fn markdown_to_html_with_options(content: &str, allow_html: bool, ctx: &mut RenderContext) -> String {
+    render_markdown(content, allow_html, ctx).html
+}
 
-pub fn markdown_to_html(content: &str) -> String {
-    // Set up options for GitHub-flavored markdown
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TABLES);
-    options.insert(Options::ENABLE_FOOTNOTES);
-    options.insert(Options::ENABLE_TASKLISTS);
+// Render a post once: the sanitised HTML, its table of contents, a plain-text
+// extract and the references it cites
+pub fn render_markdown(content: &str, allow_html: bool, ctx: &mut RenderContext) -> RenderedContent {
+    let site_config = get_site_config();
 
-    // Pre-process Obsidian wiki links
-    let processed_content = process_obsidian_syntax(content);
+    // Run the transforms configured for this site, in order
+    let pipeline = TransformPipeline::from_names(&site_config.markdown.transforms);
+    let processed_content = pipeline.preprocess(content, ctx);
 
     // Parse the markdown
-    let parser = Parser::new_ext(&processed_content, options);
+    let events: Vec<Event> = Parser::new_ext(&processed_content, markdown_options()).collect();
+    let events = pipeline.transform(events, ctx);
 
     // Write to string buffer
     let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
+    html::push_html(&mut html_output, events.into_iter());
 
-    html_output
-}
-fn html_escape(s: &str) -> String {
-    s.replace("&", "&amp;")
-        .replace("<", "&lt;")
-        .replace(">", "&gt;")
-        .replace("\"", "&quot;")
-        .replace("'", "&#39;")
-}
-fn process_obsidian_syntax(content: &str) -> String {
-    let mut result = String::new();
-
-    // Process line by line
-    for line in content.lines() {
-        let processed_line = process_wiki_links(line);
-        result.push_str(&processed_line);
-        result.push('\n');
+    let sanitize_config = &site_config.sanitize;
+    let trusted = allow_html && sanitize_config.allow_post_override;
+
+    let notebook = ctx.source_path.as_ref().is_some_and(|path| path.ends_with(".ipynb"));
+
+    let mut html = if sanitize_config.enabled && !trusted && notebook {
+        sanitize_notebook_html(&html_output, sanitize_config)
+    } else if sanitize_config.enabled && !trusted {
+        sanitize_html(&html_output, sanitize_config)
+    } else {
+        html_output
+    };
+
+    // Markup the transforms generated themselves goes back in unsanitised
+    for (i, fragment) in std::mem::take(&mut ctx.trusted_html).into_iter().enumerate() {
+        html = html.replacen(&trusted_placeholder(ctx.trusted_nonce, i), &fragment, 1);
     }
 
-    result
+    RenderedContent {
+        html,
+        toc: std::mem::take(&mut ctx.toc),
+        plain_text: markdown_to_plain_text(content),
+        bibliography: std::mem::take(&mut ctx.bibliography),
+    }
 }
-fn process_wiki_links(line: &str) -> String {
-    let mut result = String::new();
-    let mut current_pos = 0;
-
-    // Scan for [[ patterns
-    while let Some(start_pos) = line[current_pos..].find("[[") {
-        // Add text before the wiki link
-        result.push_str(&line[current_pos..current_pos + start_pos]);
-
-        // Move past the [[ characters
-        let link_start = current_pos + start_pos + 2;
-
-        // Find the closing ]]
-        if let Some(end_pos) = line[link_start..].find("]]") {
-            let link_content = &line[link_start..link_start + end_pos];
-
-            // Check for pipe character for display text
-            if let Some(pipe_pos) = link_content.find('|') {
-                let target = &link_content[0..pipe_pos];
-                let display = &link_content[pipe_pos + 1..];
-
-                // Create a regular markdown link
-                result.push_str(&format!("[{}](/notes/{})",
-                    display,
-                    target.replace(' ', "-").to_lowercase()));
-            } else {
-                // No display text, use the link content as is
-                result.push_str(&format!("[{}](/notes/{})",
-                    link_content,
-                    link_content.replace(' ', "-").to_lowercase()));
-            }
 
-            // Move past the ]] characters
-            current_pos = link_start + end_pos + 2;
-        } else {
-            // No closing ]], just add the [[ and continue
-            result.push_str("[[");
-            current_pos = link_start;
+pub(crate) fn markdown_options() -> Options {
+    // Set up options for GitHub-flavored markdown
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options
+}
+
+// The readable text of a post, without markdown syntax, code blocks,
+// raw HTML or shortcodes. Blocks are separated by newlines.
+pub fn markdown_to_plain_text(content: &str) -> String {
+    let mut text = String::with_capacity(content.len());
+    let mut in_code_block = false;
+    let content = escape_table_wiki_links(content);
+
+    for event in join_shortcode_text(Parser::new_ext(&content, markdown_options()).collect()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Text(t) | Event::Code(t) if !in_code_block => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(Tag::Paragraph)
+            | Event::End(Tag::Heading(..))
+            | Event::End(Tag::Item)
+            | Event::End(Tag::TableRow)
+            | Event::End(Tag::TableHead) => text.push('\n'),
+            Event::End(Tag::TableCell) => text.push(' '),
+            _ => {}
         }
     }
 
-    // Add any remaining text
-    result.push_str(&line[current_pos..]);
+    let text = strip_shortcodes(&strip_wiki_links(&text));
 
-    result
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-fn process_obsidian_links(text: &str) -> String {
-    let mut result = String::with_capacity(text.len() * 2);
-    let mut chars = text.char_indices();
-    let mut last_end = 0;
-
-    while let Some((i, c)) = chars.next() {
-        if c == '[' && i + 1 < text.len() && text[i+1..].starts_with("[") {
-            // Add text before the link
-            result.push_str(&text[last_end..i]);
+// Summary for a post that doesn't set one in its front matter.
+// Everything before a `<!-- more -->` marker is used when the post has one,
+// otherwise the first paragraph, shortened to `max_chars` characters.
+pub fn extract_summary(content: &str, max_chars: usize) -> String {
+    static MORE_MARKER: OnceLock<Regex> = OnceLock::new();
+    let more_marker = MORE_MARKER.get_or_init(|| Regex::new(r"<!--\s*more\s*-->").unwrap());
+
+    // Only a marker that's really HTML counts, not one shown in code
+    let marker = Parser::new_ext(content, markdown_options())
+        .into_offset_iter()
+        .find_map(|(event, range)| match event {
+            Event::Html(html) => more_marker.find(&html).map(|found| range.start + found.start()),
+            _ => None,
+        });
+
+    if let Some(marker) = marker {
+        let excerpt = paragraph_text(&content[..marker], usize::MAX);
+        if !excerpt.is_empty() {
+            return excerpt;
+        }
+    }
 
-            let start = i + 2;
+    truncate_on_word_boundary(&paragraph_text(content, 1), max_chars)
+}
 
-            // Find the end of the link (closing brackets)
-            let mut end_index = None;
-            let mut bracket_chars = text[start..].char_indices();
-            while let Some((j, ch)) = bracket_chars.next() {
-                if ch == ']' && j + 1 < text[start..].len() && text[start+j+1..].starts_with("]") {
-                    end_index = Some(start + j);
-                    break;
+// Plain text of the first `max_paragraphs` top-level paragraphs that have any
+// text, skipping headings, images, code, lists and quotes
+fn paragraph_text(content: &str, max_paragraphs: usize) -> String {
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current: Option<String> = None;
+    let mut nesting = 0;
+    let mut in_image = false;
+
+    for event in Parser::new_ext(content, markdown_options()) {
+        match event {
+            Event::Start(Tag::BlockQuote)
+            | Event::Start(Tag::List(_))
+            | Event::Start(Tag::FootnoteDefinition(_))
+            | Event::Start(Tag::Table(_)) => nesting += 1,
+            Event::End(Tag::BlockQuote)
+            | Event::End(Tag::List(_))
+            | Event::End(Tag::FootnoteDefinition(_))
+            | Event::End(Tag::Table(_)) => nesting -= 1,
+            Event::Start(Tag::Paragraph) if nesting == 0 => current = Some(String::new()),
+            Event::End(Tag::Paragraph) => {
+                if let Some(text) = current.take() {
+                    let text = strip_shortcodes(&strip_wiki_links(&text));
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+                    if !text.is_empty() {
+                        paragraphs.push(text);
+                        if paragraphs.len() >= max_paragraphs {
+                            break;
+                        }
+                    }
                 }
             }
-
-            if let Some(end) = end_index {
-                // Extract the link text
-                let link_text = &text[start..end];
-
-                // Check if there's a pipe for display text
-                if let Some(pipe_pos) = link_text.find('|') {
-                    let link = &link_text[0..pipe_pos];
-                    let display = &link_text[pipe_pos+1..];
-
-                    // Create an HTML link
-                    result.push_str(&format!("<a href=\"/notes/{}\">{}</a>",
-                        link.replace(' ', "-").to_lowercase(),
-                        display));
-                } else {
-                    // No pipe, use the link text as display text
-                    result.push_str(&format!("<a href=\"/notes/{}\">{}</a>",
-                        link_text.replace(' ', "-").to_lowercase(),
-                        link_text));
+            Event::Start(Tag::Image(..)) => in_image = true,
+            Event::End(Tag::Image(..)) => in_image = false,
+            Event::Text(t) | Event::Code(t) if !in_image => {
+                if let Some(text) = current.as_mut() {
+                    text.push_str(&t);
                 }
-
-                // Skip ahead past the closing brackets
-                last_end = end + 2;
-                // Find and skip characters until we reach last_end
-                while let Some((i, _)) = chars.next() {
-                    if i >= last_end {
-                        break;
-                    }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(text) = current.as_mut() {
+                    text.push(' ');
                 }
-            } else {
-                // No closing brackets found, just add the opening bracket as-is
-                result.push('[');
-                last_end = i + 1;
             }
+            _ => {}
         }
     }
 
-    // Add any remaining text
-    if last_end < text.len() {
-        result.push_str(&text[last_end..]);
-    }
-
-    result
+    paragraphs.join(" ")
 }
 
-fn process_inline_formatting(text: &str) -> String {
-    // If text might contain HTML or special characters, escape it first
-    let escaped_text = html_escape(text);
+// "[[Note|display]]" -> "display", "[[Note]]" -> "Note"
+fn strip_wiki_links(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
 
-    // Process markdown-style formatting
-    let text_with_links = replace_markdown_links(&escaped_text);
-    let text_with_bold = replace_bold(&text_with_links);
-    let text_with_italic = replace_italic(&text_with_bold);
+    while let Some(start) = rest.find("[[") {
+        let Some(end) = rest[start + 2..].find("]]") else {
+            break;
+        };
+
+        let link = &rest[start + 2..start + 2 + end];
+        result.push_str(&rest[..start]);
+        result.push_str(link.rsplit('|').next().unwrap_or(link));
+        rest = &rest[start + 2 + end + 2..];
+    }
 
-    text_with_italic
+    result.push_str(rest);
+    result
 }
 
-fn replace_markdown_links(text: &str) -> String {
-    let mut result = String::with_capacity(text.len() * 2);
-    let mut chars = text.char_indices();
-    let mut last_end = 0;
-
-    while let Some((i, c)) = chars.next() {
-        if c == '[' {
-            // Find the closing bracket
-            let mut text_end = None;
-            let mut bracket_depth = 1;
-            let mut bracket_chars = text[i+1..].char_indices();
-
-            while let Some((j, ch)) = bracket_chars.next() {
-                if ch == '[' {
-                    bracket_depth += 1;
-                } else if ch == ']' {
-                    bracket_depth -= 1;
-                    if bracket_depth == 0 {
-                        text_end = Some(i + 1 + j);
-                        break;
-                    }
-                }
+// Whether each line of a markdown document is part of a fenced code block,
+// fence lines included. A block is only closed by a fence of the same
+// character that's at least as long as the one that opened it.
+pub fn code_block_lines(content: &str) -> Vec<bool> {
+    let mut open: Option<(char, usize)> = None;
+
+    content.lines()
+        .map(|line| {
+            let fence = code_fence(line);
+            match (open, fence) {
+                (None, Some(fence)) => open = Some(fence),
+                (Some((c, len)), Some((closing, closing_len)))
+                    if closing == c && closing_len >= len && line.trim_start()[closing_len..].trim().is_empty() => open = None,
+                (None, None) => return false,
+                _ => {}
             }
+            true
+        })
+        .collect()
+}
 
-            if let Some(text_end) = text_end {
-                // Check if followed by (url)
-                if text_end + 1 < text.len() && text.chars().nth(text_end + 1) == Some('(') {
-                    // Find the closing parenthesis
-                    let mut url_end = None;
-                    let mut paren_depth = 1;
-                    let mut paren_chars = text[text_end+2..].char_indices();
-
-                    while let Some((j, ch)) = paren_chars.next() {
-                        if ch == '(' {
-                            paren_depth += 1;
-                        } else if ch == ')' {
-                            paren_depth -= 1;
-                            if paren_depth == 0 {
-                                url_end = Some(text_end + 2 + j);
-                                break;
-                            }
-                        }
-                    }
+// The character and length of a ``` or ~~~ fence starting the line
+pub(crate) fn code_fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start();
+    let c = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|&next| next == c).count();
 
-                    if let Some(url_end) = url_end {
-                        // Add text before the link
-                        result.push_str(&text[last_end..i]);
+    (len >= 3).then_some((c, len))
+}
 
-                        // Extract the link text and URL
-                        let link_text = &text[i+1..text_end];
-                        let url = &text[text_end+2..url_end];
+// A `|` in a table row ends the cell, even inside a wiki link, so aliases in
+// `[[Note|text]]` links in tables are escaped before parsing. pulldown-cmark
+// unescapes them in the cell's text. Code spans and blocks are left alone.
+pub fn escape_table_wiki_links(content: &str) -> String {
+    if !content.contains("[[") {
+        return content.to_string();
+    }
 
-                        // Create HTML link
-                        result.push_str(&format!("<a href=\"{}\">{}</a>", url, link_text));
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let in_code = code_block_lines(content);
+    let mut result = String::with_capacity(content.len());
+    let mut in_table = false;
 
-                        // Update position
-                        last_end = url_end + 1;
-                        // Skip characters we've processed
-                        while let Some((idx, _)) = chars.next() {
-                            if idx >= last_end {
-                                break;
-                            }
-                        }
-                        continue;
-                    }
-                }
-            }
+    for (i, line) in lines.iter().enumerate() {
+        if in_code[i] || line.trim().is_empty() {
+            in_table = false;
+        } else if !in_table && line.contains('|') {
+            in_table = lines.get(i + 1).is_some_and(|next| !in_code[i + 1] && is_table_delimiter(next));
         }
 
-        // If we get here, we didn't process a link at the current position
-        if last_end <= i {
-            result.push(c);
+        if in_table && line.contains("[[") {
+            result.push_str(&escape_wiki_link_pipes(line));
+        } else {
+            result.push_str(line);
         }
     }
 
-    // Add any remaining text (if we ended without processing a link)
-    if last_end < text.len() && last_end > 0 {
-        result.push_str(&text[last_end..]);
-    }
+    result
+}
 
-    // If we didn't process any links, return the original text
-    if result.is_empty() {
-        text.to_string()
-    } else {
-        result
-    }
+// `| --- | :---: |`, the row under a table's header
+fn is_table_delimiter(line: &str) -> bool {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+
+    line.contains('-') && line.split('|').all(|cell| {
+        let cell = cell.trim();
+        let cell = cell.strip_prefix(':').unwrap_or(cell);
+        let cell = cell.strip_suffix(':').unwrap_or(cell);
+        !cell.is_empty() && cell.chars().all(|c| c == '-')
+    })
 }
 
-fn replace_bold(text: &str) -> String {
-    let mut result = String::with_capacity(text.len());
-    let mut in_bold = false;
-    let mut i = 0;
-
-    while i < text.len() {
-        // Check for "**" pattern
-        if i + 1 < text.len() && &text[i..i+2] == "**" {
-            if in_bold {
-                result.push_str("</strong>");
-                in_bold = false;
-                i += 2;
-            } else {
-                result.push_str("<strong>");
-                in_bold = true;
-                i += 2;
+fn escape_wiki_link_pipes(line: &str) -> String {
+    let mut result = String::with_capacity(line.len() + 4);
+    let mut rest = line;
+
+    loop {
+        let code = rest.find('`');
+        let link = rest.find("[[");
+
+        match (code, link) {
+            // Code spans run to the next run of as many backticks
+            (Some(start), link) if link.map_or(true, |link| start < link) => {
+                let ticks = rest[start..].chars().take_while(|&c| c == '`').count();
+                let after = start + ticks;
+                let end = rest[after..].find(&"`".repeat(ticks)).map_or(rest.len(), |end| after + end + ticks);
+                result.push_str(&rest[..end]);
+                rest = &rest[end..];
             }
-        } else if i < text.len() {
-            // Get the character at index i
-            let c = text[i..].chars().next().unwrap();
-            result.push(c);
-            i += c.len_utf8();
-        } else {
-            break;
+            (_, Some(start)) => {
+                let Some(end) = rest[start..].find("]]").map(|end| start + end + 2) else {
+                    break;
+                };
+                result.push_str(&rest[..start]);
+                result.push_str(&rest[start..end].replace("\\|", "|").replace('|', "\\|"));
+                rest = &rest[end..];
+            }
+            _ => break,
         }
     }
 
-    // Close any open tags
-    if in_bold {
-        result.push_str("</strong>");
-    }
-
+    result.push_str(rest);
     result
 }
 
-fn replace_italic(text: &str) -> String {
+fn strip_shortcodes(text: &str) -> String {
     let mut result = String::with_capacity(text.len());
-    let mut in_italic = false;
-    let mut i = 0;
-
-    while i < text.len() {
-        // Check for single "*" pattern that's not "**"
-        if i < text.len() && text[i..].starts_with("*") &&
-           !(i + 1 < text.len() && text[i+1..].starts_with("*")) {
-            if in_italic {
-                result.push_str("</em>");
-                in_italic = false;
-                i += 1;
-            } else {
-                result.push_str("<em>");
-                in_italic = true;
-                i += 1;
-            }
-        } else if i < text.len() {
-            // Get the character at index i
-            let c = text[i..].chars().next().unwrap();
-            result.push(c);
-            i += c.len_utf8();
-        } else {
-            break;
-        }
-    }
+    let mut rest = text;
 
-    // Close any open tags
-    if in_italic {
-        result.push_str("</em>");
+    while let Some((start, end, _)) = find_shortcode(rest) {
+        result.push_str(&rest[..start]);
+        rest = &rest[end..];
     }
 
+    result.push_str(rest);
     result
 }
+
+pub fn html_escape(s: &str) -> String {
+    s.replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+        .replace("'", "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_stops_at_the_more_marker() {
+        let content = "First paragraph.\n\nSecond *one*.\n\n<!-- more -->\n\nHidden.\n";
+
+        assert_eq!(extract_summary(content, 10), "First paragraph. Second one.");
+    }
+
+    #[test]
+    fn more_markers_in_code_are_ignored() {
+        let content = "Intro about `<!-- more -->` markers.\n\n```html\n<!-- more -->\n```\n\n    <!-- more -->\n\nMore text.\n";
+
+        assert_eq!(extract_summary(content, 200), "Intro about <!-- more --> markers.");
+        assert_eq!(extract_summary(content, 12), "Intro about...");
+    }
+}
@@ -1,9 +1,34 @@
+pub mod charts;
+pub mod citations;
+pub mod client_index;
+pub mod feeds;
+pub mod facets;
 pub mod format;
+pub mod fuzzy;
+pub mod glossary;
+pub mod highlight;
 pub mod markdown;
+pub mod notebook;
+pub mod og_image;
+pub mod org;
+pub mod query;
 pub mod jd_content_loader;
+pub mod doctest;
 pub mod directory_scanner; // New module
+pub mod render_cache;
+pub mod sanitize;
+pub mod search;
+pub mod search_query;
+pub mod seo;
+pub mod shortcodes;
+pub mod sitemap;
+pub mod site_config;
+pub mod snippets;
+pub mod tags;
+pub mod transforms;
 
 pub use format::format_date;
-pub use markdown::markdown_to_html;
+pub use markdown::{markdown_to_html, markdown_to_html_with_options, render_markdown};
 pub use jd_content_loader::{load_markdown_files, markdown_to_projects};
 pub use directory_scanner::{get_cached_areas, get_cached_categories}; // Export cached functions
+pub use site_config::get_site_config;
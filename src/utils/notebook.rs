@@ -0,0 +1,231 @@
+use std::sync::OnceLock;
+use regex::Regex;
+use serde_json::Value;
+use crate::utils::markdown::html_escape;
+
+// Convert a Jupyter notebook (.ipynb) into a markdown post, so it goes
+// through the same front matter parsing and rendering as `.md` files:
+//
+// - a first raw or markdown cell starting with `---` is the front matter
+// - markdown cells are copied as they are
+// - code cells become fenced code blocks in the notebook's language, which
+//   the `syntax_highlight` transform colours like any other code block
+// - stored outputs are inlined: text as `<pre>`, HTML as is, and PNG, JPEG
+//   and SVG images as data URLs
+pub fn notebook_to_markdown(json: &str) -> Result<String, String> {
+    let notebook: Value = serde_json::from_str(json).map_err(|e| format!("invalid notebook: {}", e))?;
+
+    let cells = notebook.get("cells")
+        .and_then(Value::as_array)
+        .ok_or("notebook has no cells")?;
+
+    let language = notebook.pointer("/metadata/kernelspec/language")
+        .or_else(|| notebook.pointer("/metadata/language_info/name"))
+        .and_then(Value::as_str)
+        .unwrap_or("python");
+
+    let mut blocks: Vec<String> = Vec::new();
+
+    for (i, cell) in cells.iter().enumerate() {
+        let cell_type = cell.get("cell_type").and_then(Value::as_str).unwrap_or("");
+        let source = multiline_text(cell.get("source"));
+
+        match cell_type {
+            "raw" | "markdown" if i == 0 && source.trim_start().starts_with("---") => {
+                blocks.push(source.trim().to_string());
+            }
+            "markdown" => blocks.push(source),
+            "code" if !source.trim().is_empty() => {
+                blocks.push(fenced_code(&source, language));
+
+                for output in cell.get("outputs").and_then(Value::as_array).into_iter().flatten() {
+                    if let Some(html) = render_output(output) {
+                        blocks.push(html);
+                    }
+                }
+            }
+            // Other raw cells are meant for other export formats
+            _ => {}
+        }
+    }
+
+    Ok(blocks.join("\n\n") + "\n")
+}
+
+// Notebook text is either a string or a list of lines
+fn multiline_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+// A fence longer than any backtick run in the code
+fn fenced_code(code: &str, language: &str) -> String {
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    format!("{fence}{}\n{}\n{fence}", language, code.trim_end(), fence = fence)
+}
+
+// An output as an HTML block, preferring the richest format we can show
+fn render_output(output: &Value) -> Option<String> {
+    match output.get("output_type").and_then(Value::as_str)? {
+        "stream" => {
+            let text = multiline_text(output.get("text"));
+            let stream = output.get("name").and_then(Value::as_str).unwrap_or("stdout");
+            Some(text_output(&text, &format!("nb-stream nb-{}", stream)))
+        }
+        "error" => {
+            let traceback: Vec<String> = output.get("traceback")
+                .and_then(Value::as_array)
+                .map(|lines| lines.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                .unwrap_or_default();
+            Some(text_output(&traceback.join("\n"), "nb-error"))
+        }
+        "execute_result" | "display_data" => {
+            let data = output.get("data")?;
+
+            for mime in ["image/png", "image/jpeg"] {
+                if let Some(image) = data.get(mime) {
+                    let base64: String = multiline_text(Some(image)).split_whitespace().collect();
+                    return Some(image_output(&format!("data:{};base64,{}", mime, base64)));
+                }
+            }
+
+            if let Some(svg) = data.get("image/svg+xml") {
+                let svg = multiline_text(Some(svg));
+                return Some(image_output(&format!("data:image/svg+xml;base64,{}", base64_encode(svg.as_bytes()))));
+            }
+
+            if let Some(html) = data.get("text/html") {
+                // Blank lines would end the HTML block early
+                let html: Vec<String> = multiline_text(Some(html)).lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(str::to_string)
+                    .collect();
+                return Some(format!("<div class=\"nb-output nb-html\">\n{}\n</div>", html.join("\n")));
+            }
+
+            data.get("text/plain").map(|text| text_output(&multiline_text(Some(text)), "nb-result"))
+        }
+        _ => None,
+    }
+}
+
+fn text_output(text: &str, class: &str) -> String {
+    // Tracebacks and some libraries colour their output with ANSI escapes
+    static ANSI_RE: OnceLock<Regex> = OnceLock::new();
+    let ansi_re = ANSI_RE.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+    let text = ansi_re.replace_all(text.trim_end(), "");
+
+    format!("<pre class=\"nb-output {}\">{}</pre>", class, html_escape(&text))
+}
+
+fn image_output(src: &str) -> String {
+    format!("<div class=\"nb-output nb-image\"><img src=\"{}\" alt=\"Notebook output\" /></div>", src)
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn converts_cells_and_outputs() {
+        let notebook = json!({
+            "metadata": { "kernelspec": { "language": "python" } },
+            "cells": [
+                { "cell_type": "raw", "source": ["---\n", "title: Notebook\n", "---\n"] },
+                { "cell_type": "markdown", "source": "# Intro" },
+                {
+                    "cell_type": "code",
+                    "source": ["print('hi')\n", "1 / 0"],
+                    "outputs": [
+                        { "output_type": "stream", "name": "stderr", "text": ["a < b\n"] },
+                        { "output_type": "error", "traceback": ["\u{1b}[0;31mZeroDivisionError\u{1b}[0m", "division by zero"] },
+                    ],
+                },
+                { "cell_type": "code", "source": "  \n", "outputs": [] },
+                { "cell_type": "raw", "source": "\\LaTeX only" },
+            ],
+        });
+
+        assert_eq!(
+            notebook_to_markdown(&notebook.to_string()).unwrap(),
+            "---\ntitle: Notebook\n---\n\n# Intro\n\n```python\nprint('hi')\n1 / 0\n```\n\n\
+            <pre class=\"nb-output nb-stream nb-stderr\">a &lt; b</pre>\n\n\
+            <pre class=\"nb-output nb-error\">ZeroDivisionError\ndivision by zero</pre>\n",
+        );
+    }
+
+    #[test]
+    fn front_matter_only_comes_from_the_first_cell() {
+        let notebook = json!({
+            "metadata": { "language_info": { "name": "julia" } },
+            "cells": [
+                { "cell_type": "markdown", "source": "Text" },
+                { "cell_type": "raw", "source": "---\ntitle: Late\n---" },
+                { "cell_type": "code", "source": "x = 1" },
+            ],
+        });
+
+        assert_eq!(notebook_to_markdown(&notebook.to_string()).unwrap(), "Text\n\n```julia\nx = 1\n```\n");
+        assert!(notebook_to_markdown("{}").is_err());
+        assert!(notebook_to_markdown("not json").is_err());
+    }
+
+    #[test]
+    fn renders_rich_outputs() {
+        let png = json!({ "output_type": "display_data", "data": { "image/png": ["iVBO\n", "Rw0K\n"], "text/plain": "<Figure>" } });
+        assert_eq!(
+            render_output(&png).unwrap(),
+            "<div class=\"nb-output nb-image\"><img src=\"data:image/png;base64,iVBORw0K\" alt=\"Notebook output\" /></div>",
+        );
+
+        let svg = json!({ "output_type": "display_data", "data": { "image/svg+xml": "<svg/>" } });
+        assert!(render_output(&svg).unwrap().contains("src=\"data:image/svg+xml;base64,PHN2Zy8+\""));
+
+        let html = json!({ "output_type": "execute_result", "data": { "text/html": ["<table>\n", "\n", "<tr><td>1</td></tr>\n", "</table>"] } });
+        assert_eq!(render_output(&html).unwrap(), "<div class=\"nb-output nb-html\">\n<table>\n<tr><td>1</td></tr>\n</table>\n</div>");
+
+        let text = json!({ "output_type": "execute_result", "data": { "text/plain": "42" } });
+        assert_eq!(render_output(&text).unwrap(), "<pre class=\"nb-output nb-result\">42</pre>");
+
+        assert_eq!(render_output(&json!({ "output_type": "update_display_data" })), None);
+    }
+
+    #[test]
+    fn fences_are_longer_than_backticks_in_the_code() {
+        assert_eq!(fenced_code("x = 1\n", "python"), "```python\nx = 1\n```");
+        assert_eq!(fenced_code("s = \"````\"", "python"), "`````python\ns = \"````\"\n`````");
+    }
+
+    #[test]
+    fn base64_matches_the_rfc_vectors() {
+        for (input, expected) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foobar", "Zm9vYmFy")] {
+            assert_eq!(base64_encode(input.as_bytes()), expected);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use leptos::logging::log;
+use crate::model::Project;
+use crate::utils::markdown::html_escape;
+use crate::utils::site_config::get_site_config;
+
+// Open Graph's recommended image size
+pub const OG_WIDTH: u32 = 1200;
+pub const OG_HEIGHT: u32 = 630;
+
+// Longest title line, in characters, and how many lines fit
+const TITLE_LINE_CHARS: usize = 26;
+const TITLE_MAX_LINES: usize = 3;
+
+// Accent colour for each area (10-19, 20-29, ...), from the site palette
+pub fn area_colour(area_id: Option<u8>) -> &'static str {
+    match area_id {
+        Some(10) => "#3498db",
+        Some(20) => "#2ecc71",
+        Some(30) => "#9b59b6",
+        Some(40) => "#e67e22",
+        Some(50) => "#e74c3c",
+        _ => "#2c3e50",
+    }
+}
+
+// The social preview card for a post: title, Johnny Decimal badge and
+// category in the area's colour, and the author
+pub fn og_image_svg(project: &Project, author: &str) -> String {
+    let colour = area_colour(project.jd_category.as_ref().map(|c| c.area_id));
+    let category = project.jd_category.as_ref().map(|c| c.name.clone()).unwrap_or_default();
+    let lines = wrap_title(&project.title);
+
+    // Centre the title block vertically between the badge and the footer
+    let line_height = 78;
+    let first_baseline = 330 - (lines.len() as i32 - 1) * line_height / 2;
+
+    let title: String = lines.iter()
+        .enumerate()
+        .map(|(i, line)| format!(
+            "<text x=\"80\" y=\"{}\" class=\"title\">{}</text>",
+            first_baseline + i as i32 * line_height,
+            html_escape(line)
+        ))
+        .collect();
+
+    let badge_width = 40 + project.jd_identifier.chars().count() * 22;
+    let badge = if project.jd_identifier.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "<rect x=\"80\" y=\"80\" width=\"{}\" height=\"64\" rx=\"12\" fill=\"{}\"/>\
+             <text x=\"{}\" y=\"124\" class=\"badge\" text-anchor=\"middle\">{}</text>",
+            badge_width,
+            colour,
+            80 + badge_width / 2,
+            html_escape(&project.jd_identifier)
+        )
+    };
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">
+<style>
+text {{ font-family: 'DejaVu Sans', Arial, sans-serif; }}
+.title {{ font-size: 64px; font-weight: bold; fill: #1f2933; }}
+.badge {{ font-size: 36px; font-weight: bold; fill: #ffffff; }}
+.category {{ font-size: 32px; fill: {colour}; }}
+.author {{ font-size: 30px; fill: #52606d; }}
+</style>
+<rect width="{w}" height="{h}" fill="#ffffff"/>
+<rect width="24" height="{h}" fill="{colour}"/>
+{badge}
+<text x="{category_x}" y="124" class="category">{category}</text>
+{title}
+<line x1="80" y1="520" x2="{line_end}" y2="520" stroke="#e4e7eb" stroke-width="2"/>
+<text x="80" y="572" class="author">{author}</text>
+</svg>"##,
+        w = OG_WIDTH,
+        h = OG_HEIGHT,
+        colour = colour,
+        badge = badge,
+        category_x = if badge.is_empty() { 80 } else { 80 + badge_width + 24 },
+        category = html_escape(&category),
+        title = title,
+        line_end = OG_WIDTH - 80,
+        author = html_escape(author),
+    )
+}
+
+// Break the title into lines at word boundaries, ending with an ellipsis
+// when it doesn't fit
+fn wrap_title(title: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for word in title.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > TITLE_LINE_CHARS {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    if lines.len() > TITLE_MAX_LINES {
+        lines.truncate(TITLE_MAX_LINES);
+        let last = &mut lines[TITLE_MAX_LINES - 1];
+        let kept: String = last.chars().take(TITLE_LINE_CHARS - 1).collect();
+        *last = format!("{}…", kept.trim_end());
+    }
+
+    lines
+}
+
+// The PNG for a post, rendered once per distinct card and cached on disk
+// under a hash of its SVG, so edits to the title or category re-render it
+pub fn og_image_png(project: &Project) -> Result<Vec<u8>, String> {
+    let config = get_site_config();
+    let svg = og_image_svg(project, &config.site.author);
+
+    let mut hasher = DefaultHasher::new();
+    svg.hash(&mut hasher);
+    let cache_dir = PathBuf::from(&config.og_images.cache_dir);
+    let cached = cache_dir.join(format!("{:016x}.png", hasher.finish()));
+
+    if let Ok(png) = fs::read(&cached) {
+        return Ok(png);
+    }
+
+    let png = render_png(&svg)?;
+
+    // A failed cache write only costs a re-render next time
+    if fs::create_dir_all(&cache_dir).and_then(|_| fs::write(&cached, &png)).is_err() {
+        log!("Couldn't cache social preview image at {}", cached.display());
+    }
+
+    Ok(png)
+}
+
+#[cfg(feature = "ssr")]
+fn render_png(svg: &str) -> Result<Vec<u8>, String> {
+    use resvg::{tiny_skia, usvg};
+
+    let mut options = usvg::Options::default();
+    let fonts = options.fontdb_mut();
+    fonts.load_system_fonts();
+    fonts.load_fonts_dir("public/fonts");
+    fonts.set_sans_serif_family("DejaVu Sans");
+
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| e.to_string())?;
+    let mut pixmap = tiny_skia::Pixmap::new(OG_WIDTH, OG_HEIGHT).ok_or("couldn't allocate image")?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "ssr"))]
+fn render_png(_svg: &str) -> Result<Vec<u8>, String> {
+    Err("social preview images are only rendered on the server".to_string())
+}
@@ -0,0 +1,349 @@
+use std::sync::OnceLock;
+use regex::{Captures, Regex};
+
+// The patterns the converter matches lines and inline markup with, compiled once
+struct OrgSyntax {
+    keyword: Regex,
+    block: Regex,
+    drawer: Regex,
+    property: Regex,
+    heading: Regex,
+    list: Regex,
+    code: Regex,
+    link: Regex,
+    // Bold, italic and strikethrough, with their markdown replacements
+    emphasis: Vec<(Regex, &'static str)>,
+}
+
+fn syntax() -> &'static OrgSyntax {
+    static SYNTAX: OnceLock<OrgSyntax> = OnceLock::new();
+
+    SYNTAX.get_or_init(|| {
+        let emphasis = [(r"\*", "**"), (r"/", "*"), (r"\+", "~~")].into_iter()
+            .map(|(marker, replacement)| {
+                let pattern = format!(
+                    r"(^|[\s(\x22'{{])({m})([^\s{m}](?:[^{m}]*?[^\s{m}])?){m}($|[\s)\x22'.,;:!?}}-])",
+                    m = marker
+                );
+                (Regex::new(&pattern).unwrap(), replacement)
+            })
+            .collect();
+
+        OrgSyntax {
+            keyword: Regex::new(r"(?i)^#\+([A-Z_]+):\s*(.*)$").unwrap(),
+            block: Regex::new(r"(?i)^#\+BEGIN_([A-Z]+)\s*(.*)$").unwrap(),
+            drawer: Regex::new(r"^:([A-Za-z_-]+):\s*$").unwrap(),
+            property: Regex::new(r"^:([A-Za-z_-]+):\s+(.*)$").unwrap(),
+            heading: Regex::new(r"^(\*+)\s+(.*?)(?:\s+:[\w@#%:]+:)?\s*$").unwrap(),
+            list: Regex::new(r"^(\s*)(?:[-+]|(\d+)[.)])\s+(.*)$").unwrap(),
+            code: Regex::new(r"(^|[\s(\x22'{])([=~])([^\s=~](?:.*?[^\s])?)([=~])($|[\s)\x22'.,;:!?}-])").unwrap(),
+            link: Regex::new(r"\[\[([^\]]+)\](?:\[([^\]]+)\])?\]").unwrap(),
+            emphasis,
+        }
+    })
+}
+
+// Convert an Org-mode document into a markdown post, so it goes through the
+// same front matter parsing and markdown transforms as `.md` files.
+//
+// `#+TITLE`, `#+DATE`, `#+FILETAGS`, `#+DESCRIPTION` and a property drawer
+// before the first heading become front matter. Headings, lists, emphasis,
+// links, tables, src/example/quote blocks and horizontal rules are
+// converted; comments, other keywords and drawers are dropped.
+pub fn org_to_markdown(source: &str) -> String {
+    let mut front_matter: Vec<(String, String)> = Vec::new();
+    let mut body: Vec<String> = Vec::new();
+    let mut lines = source.lines();
+    let mut seen_heading = false;
+    // With `org-adapt-indentation`, a section's text is indented one space
+    // past its heading's stars; that indentation means nothing in markdown
+    let mut section_indent = 0;
+    let mut table: Vec<String> = Vec::new();
+    let syntax = syntax();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        // Tables are collected and converted once they end
+        if trimmed.starts_with('|') {
+            table.push(trimmed.to_string());
+            continue;
+        } else if !table.is_empty() {
+            body.extend(convert_table(&table));
+            table.clear();
+        }
+
+        if let Some(caps) = syntax.block.captures(trimmed) {
+            let kind = caps[1].to_uppercase();
+            let end = format!("#+END_{}", kind);
+            let mut content = Vec::new();
+
+            for block_line in lines.by_ref() {
+                if block_line.trim().eq_ignore_ascii_case(&end) {
+                    break;
+                }
+                content.push(block_line);
+            }
+
+            body.extend(convert_block(&kind, caps[2].trim(), &content));
+            continue;
+        }
+
+        if let Some(caps) = syntax.keyword.captures(trimmed) {
+            let value = caps[2].trim();
+            match caps[1].to_uppercase().as_str() {
+                "TITLE" => front_matter.push(("title".to_string(), quote(value))),
+                "DATE" => front_matter.push(("date".to_string(), org_date(value))),
+                "DESCRIPTION" => front_matter.push(("summary".to_string(), quote(value))),
+                "FILETAGS" => {
+                    let tags: Vec<&str> = value.split([':', ' ']).filter(|t| !t.is_empty()).collect();
+                    front_matter.push(("tags".to_string(), format!("[{}]", tags.join(", "))));
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        // Drawers; properties before the first heading describe the post
+        if let Some(caps) = syntax.drawer.captures(trimmed) {
+            let is_properties = caps[1].eq_ignore_ascii_case("PROPERTIES");
+
+            for drawer_line in lines.by_ref() {
+                let drawer_line = drawer_line.trim();
+                if drawer_line.eq_ignore_ascii_case(":END:") {
+                    break;
+                }
+                if is_properties && !seen_heading {
+                    if let Some(property) = syntax.property.captures(drawer_line) {
+                        front_matter.push((property[1].to_lowercase(), property[2].trim().to_string()));
+                    }
+                }
+            }
+            continue;
+        }
+
+        // Comments
+        if trimmed == "#" || trimmed.starts_with("# ") {
+            continue;
+        }
+
+        if let Some(caps) = syntax.heading.captures(line) {
+            seen_heading = true;
+            section_indent = caps[1].len() + 1;
+            body.push(format!("{} {}", "#".repeat(caps[1].len().min(6)), convert_inline(&caps[2])));
+            continue;
+        }
+
+        if trimmed.len() >= 5 && trimmed.chars().all(|c| c == '-') {
+            body.push("---".to_string());
+            continue;
+        }
+
+        // Nested lists keep their indentation relative to the section
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let line = &line[indent.min(section_indent)..];
+
+        if let Some(caps) = syntax.list.captures(line) {
+            let marker = match caps.get(2) {
+                Some(number) => format!("{}.", number.as_str()),
+                None => "-".to_string(),
+            };
+            let item = caps[3].replacen("[X]", "[x]", 1).replacen("[-]", "[ ]", 1);
+            body.push(format!("{}{} {}", &caps[1], marker, convert_inline(&item)));
+            continue;
+        }
+
+        // Indented text would otherwise become a markdown code block
+        body.push(convert_inline(line.trim_start()));
+    }
+
+    if !table.is_empty() {
+        body.extend(convert_table(&table));
+    }
+
+    let body = body.join("\n");
+    if front_matter.is_empty() {
+        return body;
+    }
+
+    let front_matter: Vec<String> = front_matter.iter()
+        .map(|(key, value)| format!("{}: {}", key, value))
+        .collect();
+
+    format!("---\n{}\n---\n\n{}", front_matter.join("\n"), body)
+}
+
+// `<2024-03-01 Fri>` or `[2024-03-01 Fri 10:00]` -> 2024-03-01
+fn org_date(value: &str) -> String {
+    let value = value.trim_matches(['<', '>', '[', ']']);
+    value.split_whitespace().next().unwrap_or(value).to_string()
+}
+
+// Front matter values are unquoted once, so inner quotes survive
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value)
+}
+
+fn convert_block(kind: &str, args: &str, content: &[&str]) -> Vec<String> {
+    let content = dedent(content);
+
+    match kind {
+        "SRC" | "EXAMPLE" => {
+            // Header arguments (`:results output`) aren't part of the language
+            let language = if kind == "SRC" {
+                args.split_whitespace().next().filter(|l| !l.starts_with(':')).unwrap_or("")
+            } else {
+                ""
+            };
+            let longest_run = content.iter()
+                .flat_map(|line| line.split(|c| c != '`').map(str::len))
+                .max()
+                .unwrap_or(0);
+            let fence = "`".repeat(longest_run.max(2) + 1);
+
+            let mut block = vec![format!("{}{}", fence, language)];
+            block.extend(content);
+            block.push(fence);
+            block
+        }
+        "QUOTE" => content.iter()
+            .map(|line| format!("> {}", convert_inline(line)).trim_end().to_string())
+            .collect(),
+        _ => content.iter().map(|line| convert_inline(line)).collect(),
+    }
+}
+
+fn dedent(lines: &[&str]) -> Vec<String> {
+    let indent = lines.iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines.iter()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end().to_string())
+        .collect()
+}
+
+// Org tables to pipe tables. Markdown tables need a header, so the first row
+// is used as one when the table has no rule under it.
+fn convert_table(rows: &[String]) -> Vec<String> {
+    let is_rule = |row: &str| row.starts_with("|-");
+    let cells = |row: &str| -> Vec<String> {
+        row.trim_matches('|').split('|').map(|cell| convert_inline(cell.trim())).collect()
+    };
+
+    let data: Vec<Vec<String>> = rows.iter().filter(|row| !is_rule(row)).map(|row| cells(row)).collect();
+    let Some(columns) = data.iter().map(Vec::len).max() else {
+        return Vec::new();
+    };
+
+    let mut table = Vec::new();
+    for (i, row) in data.iter().enumerate() {
+        let mut row = row.clone();
+        row.resize(columns, String::new());
+        table.push(format!("| {} |", row.join(" | ")));
+
+        if i == 0 {
+            table.push(format!("|{}", " --- |".repeat(columns)));
+        }
+    }
+
+    // Keep the table apart from the paragraph that follows
+    table.push(String::new());
+    table
+}
+
+// Links, verbatim/code and emphasis within a line
+fn convert_inline(text: &str) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+
+    // Verbatim and code spans are copied as they are
+    for caps in syntax().code.captures_iter(text) {
+        if caps[2] != caps[4] {
+            continue;
+        }
+        let whole = caps.get(0).unwrap();
+        result.push_str(&convert_markup(&text[last..whole.start()]));
+        result.push_str(&format!("{}`{}`{}", &caps[1], &caps[3], &caps[5]));
+        last = whole.end();
+    }
+
+    result.push_str(&convert_markup(&text[last..]));
+    result
+}
+
+fn convert_markup(text: &str) -> String {
+    let syntax = syntax();
+    let text = syntax.link.replace_all(text, |caps: &Captures| {
+        convert_link(&caps[1], caps.get(2).map(|d| d.as_str()))
+    });
+
+    let mut text = text.to_string();
+    for (re, replacement) in &syntax.emphasis {
+        text = re.replace_all(&text, |caps: &Captures| {
+            format!("{}{}{}{}{}", &caps[1], replacement, &caps[3], replacement, &caps[4])
+        }).to_string();
+    }
+
+    text
+}
+
+// Web links become markdown links; links to other posts and headings
+// become wiki links so they resolve the same way
+fn convert_link(target: &str, description: Option<&str>) -> String {
+    let is_url = ["http://", "https://", "mailto:"].iter().any(|scheme| target.starts_with(scheme));
+
+    if is_url {
+        return match description {
+            Some(description) => format!("[{}]({})", description, target),
+            None => format!("<{}>", target),
+        };
+    }
+
+    let target = if let Some(heading) = target.strip_prefix('*') {
+        return format!("[[#{}|{}]]", heading, description.unwrap_or(heading));
+    } else {
+        let file = target.strip_prefix("file:").unwrap_or(target);
+        let file = file.rsplit('/').next().unwrap_or(file);
+        file.strip_suffix(".org").unwrap_or(file).replace("::*", "#")
+    };
+
+    match description {
+        Some(description) => format!("[[{}|{}]]", target, description),
+        None => format!("[[{}]]", target),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indented_sections_stay_paragraphs_and_lists() {
+        let org = "* Heading\n  Some *bold* text\n  over two lines.\n\n  - item\n    - nested\n** Deeper\n   Indented body with =code=.\n";
+
+        assert_eq!(org_to_markdown(org), "# Heading\nSome **bold** text\nover two lines.\n\n- item\n  - nested\n## Deeper\nIndented body with `code`.");
+    }
+
+    #[test]
+    fn keywords_become_front_matter() {
+        let org = "#+TITLE: A \"quoted\" title\n#+DATE: <2024-03-01 Fri>\n#+FILETAGS: :rust:org:\n\nText with [[https://example.com][a link]].\n";
+
+        assert_eq!(
+            org_to_markdown(org),
+            "---\ntitle: \"A \"quoted\" title\"\ndate: 2024-03-01\ntags: [rust, org]\n---\n\n\nText with [a link](https://example.com)."
+        );
+    }
+
+    #[test]
+    fn src_blocks_keep_their_language_for_highlighting() {
+        let org = "#+BEGIN_SRC rust :results output\n  let x = 1;\n#+END_SRC\n";
+        let markdown = org_to_markdown(org);
+        assert_eq!(markdown, "```rust\nlet x = 1;\n```");
+
+        let html = crate::utils::markdown::markdown_to_html(&markdown);
+        assert!(html.contains("<code class=\"language-rust\"><span class=\"hl-keyword\">let</span>"), "{}", html);
+    }
+}
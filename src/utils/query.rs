@@ -0,0 +1,114 @@
+use std::cmp::Reverse;
+use crate::model::Project;
+use crate::utils::shortcodes::{tokenize, unquote};
+use crate::utils::tags::tag_matches;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuerySort {
+    DateDesc,
+    DateAsc,
+    Title,
+    JdIdentifier,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryFormat {
+    List,
+    Table,
+}
+
+// A query over the post index, as written in a ```posts block:
+//
+//     tag:rust category:11 sort:date limit:5 format:table
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostQuery {
+    pub tags: Vec<String>,
+    pub area: Option<u8>,
+    pub category: Option<u8>,
+    pub sort: QuerySort,
+    pub limit: Option<usize>,
+    pub format: QueryFormat,
+}
+
+impl Default for PostQuery {
+    fn default() -> Self {
+        PostQuery {
+            tags: Vec::new(),
+            area: None,
+            category: None,
+            sort: QuerySort::DateDesc,
+            limit: None,
+            format: QueryFormat::List,
+        }
+    }
+}
+
+impl PostQuery {
+    pub fn parse(input: &str) -> Result<PostQuery, String> {
+        let mut query = PostQuery::default();
+
+        for token in tokenize(input) {
+            let (key, value) = token.split_once(':')
+                .ok_or_else(|| format!("expected `key:value`, found `{}`", token))?;
+            let value = unquote(value);
+
+            match key.to_lowercase().as_str() {
+                "tag" => query.tags.push(value.to_string()),
+                "area" => query.area = Some(parse_number(key, value)?),
+                "category" | "cat" => query.category = Some(parse_number(key, value)?),
+                "limit" => query.limit = Some(parse_number(key, value)?),
+                "sort" => {
+                    query.sort = match value.to_lowercase().as_str() {
+                        "date" | "-date" => QuerySort::DateDesc,
+                        "+date" | "oldest" => QuerySort::DateAsc,
+                        "title" => QuerySort::Title,
+                        "jd" | "id" => QuerySort::JdIdentifier,
+                        other => return Err(format!("unknown sort `{}`", other)),
+                    }
+                }
+                "format" => {
+                    query.format = match value.to_lowercase().as_str() {
+                        "list" => QueryFormat::List,
+                        "table" => QueryFormat::Table,
+                        other => return Err(format!("unknown format `{}`", other)),
+                    }
+                }
+                other => return Err(format!("unknown query field `{}`", other)),
+            }
+        }
+
+        Ok(query)
+    }
+
+    pub fn matches(&self, project: &Project) -> bool {
+        let category = project.jd_category.as_ref();
+
+        self.tags.iter().all(|tag| project.tech_stack.iter().any(|t| tag_matches(t, tag)))
+            && self.area.map_or(true, |area| category.is_some_and(|c| c.area_id == area))
+            && self.category.map_or(true, |cat| category.is_some_and(|c| c.id == cat))
+    }
+
+    pub fn run<'p>(&self, projects: &'p [Project]) -> Vec<&'p Project> {
+        let mut results: Vec<&Project> = projects.iter()
+            .filter(|p| self.matches(p))
+            .collect();
+
+        match self.sort {
+            QuerySort::DateDesc => results.sort_by_key(|p| Reverse(p.created_at)),
+            QuerySort::DateAsc => results.sort_by_key(|p| p.created_at),
+            QuerySort::Title => results.sort_by_key(|p| p.title.to_lowercase()),
+            QuerySort::JdIdentifier => results.sort_by(|a, b| a.jd_identifier.cmp(&b.jd_identifier)),
+        }
+
+        if let Some(limit) = self.limit {
+            results.truncate(limit);
+        }
+
+        results
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse::<T>()
+        .map_err(|_| format!("`{}` expects a number, found `{}`", key, value))
+}
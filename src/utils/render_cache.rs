@@ -0,0 +1,94 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use crate::model::{Project, RenderedContent};
+use crate::utils::charts::{find_data_files, resolve_data_file};
+use crate::utils::format::format_date;
+use crate::utils::markdown::render_markdown;
+use crate::utils::snippets::find_snippet_includes;
+use crate::utils::transforms::RenderContext;
+
+// Rendered posts keyed by a hash of everything that affects their output.
+// Content is reloaded on every request, so unchanged posts are served from
+// here and only new or edited posts are rendered again.
+static CACHE: Mutex<Option<HashMap<u64, RenderedContent>>> = Mutex::new(None);
+
+// Render every project's markdown, reusing cached output where the content hasn't changed
+pub fn render_projects(projects: &mut [Project]) {
+    // Shortcodes, links and post queries render other posts' metadata,
+    // so a change to any of it invalidates every entry
+    let index_hash = project_index_hash(projects);
+
+    let mut cache = CACHE.lock().unwrap();
+    let previous = cache.take().unwrap_or_default();
+    let mut current = HashMap::with_capacity(projects.len());
+
+    let rendered: Vec<RenderedContent> = projects.iter()
+        .map(|project| {
+            let key = content_hash(project, index_hash);
+
+            let content = previous.get(&key).cloned().unwrap_or_else(|| {
+                let mut ctx = RenderContext::new(projects).with_source_path(&project.original_path);
+                let content = render_markdown(&project.content, project.allow_html, &mut ctx);
+
+                for problem in &ctx.diagnostics {
+                    println!("{}: {}", project.original_path, problem);
+                }
+
+                content
+            });
+
+            current.insert(key, content.clone());
+            content
+        })
+        .collect();
+
+    // Only keep entries for the content we just loaded
+    *cache = Some(current);
+
+    for (project, content) in projects.iter_mut().zip(rendered) {
+        project.rendered = Some(content);
+    }
+}
+
+// Drop all cached output, e.g. after the site config or transforms change
+pub fn clear_render_cache() {
+    *CACHE.lock().unwrap() = None;
+}
+
+fn content_hash(project: &Project, index_hash: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    project.content.hash(&mut hasher);
+    project.allow_html.hash(&mut hasher);
+    index_hash.hash(&mut hasher);
+
+    // Included snippet files and CSV files read by data blocks can change
+    // without the post changing, so their contents are part of the key
+    for (_, include) in find_snippet_includes(&project.content) {
+        include.and_then(|include| include.read()).ok().hash(&mut hasher);
+    }
+    for file in find_data_files(&project.content) {
+        resolve_data_file(Some(&project.original_path), &file)
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+fn project_index_hash(projects: &[Project]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for project in projects {
+        project.slug.hash(&mut hasher);
+        project.title.hash(&mut hasher);
+        project.jd_identifier.hash(&mut hasher);
+        project.tech_stack.hash(&mut hasher);
+        // Only the day is shown, and undated posts get the current time on
+        // every load, so hashing the full time would never hit the cache
+        format_date(project.created_at).hash(&mut hasher);
+        project.jd_category.as_ref().map(|c| (c.id, c.area_id, &c.name)).hash(&mut hasher);
+    }
+    hasher.finish()
+}
@@ -0,0 +1,424 @@
+use crate::utils::shortcodes::is_embed_url;
+use crate::utils::site_config::SanitizeConfig;
+
+// Tags that are dropped together with everything inside them when not allowed
+const DROP_CONTENT_TAGS: &[&str] = &[
+    "script", "style", "iframe", "object", "embed", "noscript", "template",
+    "textarea", "title", "xmp", "svg", "math",
+];
+
+// Attributes whose values are URLs and need their scheme checked
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "cite", "action", "formaction", "poster", "xlink:href", "data-embed-src"];
+
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+// Strip everything from rendered HTML that isn't on the site's allowlist.
+// Comments, doctypes and processing instructions are always removed,
+// event handler attributes are always dropped and URL attributes must use
+// an allowed scheme (or be relative).
+pub fn sanitize_html(html: &str, config: &SanitizeConfig) -> String {
+    sanitize_html_with(html, config, false)
+}
+
+// Notebook outputs carry their images as base64 data URLs, so for notebooks
+// those are also allowed in `<img src>`, whatever the scheme allowlist says
+pub fn sanitize_notebook_html(html: &str, config: &SanitizeConfig) -> String {
+    sanitize_html_with(html, config, true)
+}
+
+fn sanitize_html_with(html: &str, config: &SanitizeConfig, inline_images: bool) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(lt_pos) = rest.find('<') {
+        result.push_str(&rest[..lt_pos]);
+        rest = &rest[lt_pos..];
+
+        // Comments
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+
+        // Doctypes, CDATA and processing instructions
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        match parse_tag(rest) {
+            Some((tag, len)) => {
+                rest = &rest[len..];
+
+                if is_allowed_tag(&tag.name, config) {
+                    result.push_str(&render_tag(&tag, config, inline_images));
+                } else if !tag.closing && !tag.self_closing
+                    && DROP_CONTENT_TAGS.contains(&tag.name.as_str()) {
+                    rest = skip_past_closing_tag(rest, &tag.name);
+                }
+            }
+            None => {
+                // Not a tag, so keep the bracket as text
+                result.push_str("&lt;");
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn is_allowed_tag(name: &str, config: &SanitizeConfig) -> bool {
+    config.allowed_tags.iter().any(|t| t.eq_ignore_ascii_case(name))
+}
+
+fn is_allowed_attribute(name: &str, config: &SanitizeConfig) -> bool {
+    // Event handlers are never allowed, whatever the config says
+    if name.starts_with("on") {
+        return false;
+    }
+    config.allowed_attributes.iter().any(|a| a.eq_ignore_ascii_case(name))
+}
+
+// Parse a tag starting at `<`. Returns the tag and the number of bytes consumed.
+fn parse_tag(input: &str) -> Option<(Tag, usize)> {
+    let bytes = input.as_bytes();
+    let mut i = 1;
+
+    let closing = bytes.get(i) == Some(&b'/');
+    if closing {
+        i += 1;
+    }
+
+    // Tag names must start with a letter
+    if !bytes.get(i).is_some_and(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let name_start = i;
+    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'-' || bytes[i] == b':') {
+        i += 1;
+    }
+    let name = input[name_start..i].to_ascii_lowercase();
+
+    let mut attributes = Vec::new();
+    let mut self_closing = false;
+
+    loop {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+
+        match bytes.get(i) {
+            None => return None, // Unterminated tag
+            Some(b'>') => {
+                i += 1;
+                break;
+            }
+            Some(b'/') => {
+                self_closing = true;
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        self_closing = false;
+
+        // Attribute name
+        let attr_start = i;
+        while i < bytes.len()
+            && !bytes[i].is_ascii_whitespace()
+            && !matches!(bytes[i], b'=' | b'>' | b'/') {
+            i += 1;
+        }
+        let attr_name = input[attr_start..i].to_ascii_lowercase();
+
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+
+        // Attribute value
+        let mut value = None;
+        if bytes.get(i) == Some(&b'=') {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+
+            match bytes.get(i) {
+                Some(&quote) if quote == b'"' || quote == b'\'' => {
+                    let value_start = i + 1;
+                    let value_end = input[value_start..].find(quote as char)? + value_start;
+                    value = Some(input[value_start..value_end].to_string());
+                    i = value_end + 1;
+                }
+                _ => {
+                    let value_start = i;
+                    while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                        i += 1;
+                    }
+                    value = Some(input[value_start..i].to_string());
+                }
+            }
+        }
+
+        if !attr_name.is_empty() {
+            attributes.push((attr_name, value));
+        }
+    }
+
+    Some((Tag { name, closing, self_closing, attributes }, i))
+}
+
+fn render_tag(tag: &Tag, config: &SanitizeConfig, inline_images: bool) -> String {
+    if tag.closing {
+        return format!("</{}>", tag.name);
+    }
+
+    let mut html = format!("<{}", tag.name);
+
+    for (name, value) in &tag.attributes {
+        if !is_allowed_attribute(name, config) {
+            continue;
+        }
+
+        match value {
+            Some(value) => {
+                let inline_image = inline_images && tag.name == "img" && name == "src" && is_inline_image(value);
+                if URL_ATTRIBUTES.contains(&name.as_str()) && !is_safe_url(value, config) && !inline_image {
+                    continue;
+                }
+                // Embed placeholders only become iframes of known providers
+                if name == "data-embed-src" && !is_embed_url(&decode_entities(value)) {
+                    continue;
+                }
+                html.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+            }
+            None => {
+                html.push(' ');
+                html.push_str(name);
+            }
+        }
+    }
+
+    if tag.self_closing {
+        html.push_str(" />");
+    } else {
+        html.push('>');
+    }
+
+    html
+}
+
+// Quote characters are escaped, existing entities are left alone
+fn escape_attribute(value: &str) -> String {
+    value.replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn is_safe_url(url: &str, config: &SanitizeConfig) -> bool {
+    // Browsers ignore whitespace and control characters inside schemes and
+    // accept entity-encoded characters, so normalise before checking
+    let normalised: String = decode_entities(url)
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_lowercase();
+
+    // Relative URLs and fragments are always fine
+    let scheme_end = match normalised.find([':', '/', '?', '#']) {
+        Some(pos) if normalised[pos..].starts_with(':') => pos,
+        _ => return true,
+    };
+
+    let scheme = &normalised[..scheme_end];
+    config.allowed_url_schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme))
+}
+
+// Base64 data URLs for images, as notebook outputs use. Images can't run scripts.
+fn is_inline_image(url: &str) -> bool {
+    const IMAGE_TYPES: &[&str] = &["png", "jpeg", "gif", "webp", "svg+xml"];
+
+    url.strip_prefix("data:image/")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .is_some_and(|(image_type, data)| {
+            IMAGE_TYPES.contains(&image_type)
+                && data.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'='))
+        })
+}
+
+// Decode numeric character references and the named entities that matter for scheme checks
+fn decode_entities(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(amp_pos) = rest.find('&') {
+        result.push_str(&rest[..amp_pos]);
+        rest = &rest[amp_pos + 1..];
+
+        // Entities may omit the trailing semicolon, and a numeric one ends at
+        // the first character that isn't a digit, as in `&#58alert(1)`
+        let digits = |start: usize, radix: u32| {
+            start + rest[start..].find(|c: char| !c.is_digit(radix)).unwrap_or(rest.len() - start)
+        };
+        let end = if rest.starts_with("#x") || rest.starts_with("#X") {
+            digits(2, 16)
+        } else if rest.starts_with('#') {
+            digits(1, 10)
+        } else {
+            rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len())
+        };
+        let entity = &rest[..end];
+
+        let decoded = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+        } else if let Some(dec) = entity.strip_prefix('#') {
+            dec.parse::<u32>().ok().and_then(char::from_u32)
+        } else {
+            match entity.to_lowercase().as_str() {
+                "colon" => Some(':'),
+                "tab" => Some('\t'),
+                "newline" => Some('\n'),
+                "amp" => Some('&'),
+                _ => None,
+            }
+        };
+
+        match decoded {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[end..];
+                if rest.starts_with(';') {
+                    rest = &rest[1..];
+                }
+            }
+            None => result.push('&'),
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn skip_past_closing_tag<'a>(input: &'a str, name: &str) -> &'a str {
+    let lower = input.to_ascii_lowercase();
+    let closing = format!("</{}", name);
+
+    match lower.find(&closing) {
+        Some(pos) => {
+            let after = pos + closing.len();
+            lower[after..].find('>').map_or("", |end| &input[after + end + 1..])
+        }
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(html: &str) -> String {
+        sanitize_html(html, &SanitizeConfig::default())
+    }
+
+    #[test]
+    fn keeps_allowed_markup() {
+        let html = r#"<p class="intro">Hello <a href="https://example.com/a?b=1#c">link</a> <em>there</em></p>"#;
+        assert_eq!(sanitize(html), html);
+        assert_eq!(sanitize(r#"<a href="/projects/x">x</a>"#), r#"<a href="/projects/x">x</a>"#);
+    }
+
+    #[test]
+    fn drops_javascript_urls_however_written() {
+        for href in [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            " javascript:alert(1)",
+            "java\tscript:alert(1)",
+            "jav&#x61;script:alert(1)",
+            "jav&#97;script:alert(1)",
+            "javascript&colon;alert(1)",
+            "&#106&#97&#118&#97&#115&#99&#114&#105&#112&#116&#58alert(1)",
+            "javascript&#x3A alert(1)",
+            "javascript&#0000058;alert(1)",
+        ] {
+            let html = sanitize(&format!(r#"<a href="{}">x</a>"#, href));
+            assert_eq!(html, "<a>x</a>", "{}", href);
+        }
+    }
+
+    #[test]
+    fn drops_event_handlers() {
+        assert_eq!(sanitize(r#"<img src="a.png" onerror="alert(1)">"#), r#"<img src="a.png">"#);
+        assert_eq!(sanitize(r#"<p ONCLICK="alert(1)" onmouseover=alert(1)>x</p>"#), "<p>x</p>");
+
+        let mut config = SanitizeConfig::default();
+        config.allowed_attributes.push("onclick".to_string());
+        assert_eq!(sanitize_html(r#"<p onclick="alert(1)">x</p>"#, &config), "<p>x</p>");
+    }
+
+    #[test]
+    fn drops_scripts_with_their_content() {
+        assert_eq!(sanitize("<p>a</p><script>alert(1)</script><p>b</p>"), "<p>a</p><p>b</p>");
+        assert_eq!(sanitize("<p>a</p><SCRIPT>alert(1)</script ><p>b</p>"), "<p>a</p><p>b</p>");
+        // An unclosed script swallows the rest of the document
+        assert_eq!(sanitize("<p>a</p><script>alert(1)<p>b</p>"), "<p>a</p>");
+    }
+
+    #[test]
+    fn drops_scripts_inside_svg() {
+        let html = sanitize("<svg><script>alert(1)</script></svg><p>after</p>");
+        assert!(!html.contains("script") && !html.contains("alert"), "{}", html);
+        assert!(html.ends_with("<p>after</p>"), "{}", html);
+    }
+
+    #[test]
+    fn keeps_embed_sources_only_for_known_providers() {
+        let placeholder = |src: &str| sanitize(&format!(r#"<div class="embed-placeholder" data-embed-src="{}"></div>"#, src));
+
+        let youtube = "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?start=30";
+        assert!(placeholder(youtube).contains(youtube));
+        for src in [
+            "https://evil.example/embed",
+            "https://www.youtube-nocookie.com.evil.example/embed/x",
+            "https://gist.github.com@evil.example/x",
+            "javascript:alert(1)",
+        ] {
+            assert_eq!(placeholder(src), r#"<div class="embed-placeholder"></div>"#, "{}", src);
+        }
+    }
+
+    #[test]
+    fn allows_data_images_only_in_notebook_img_tags() {
+        let config = SanitizeConfig::default();
+        let image = r#"<img src="data:image/png;base64,iVBORw0KGgo=" />"#;
+
+        assert_eq!(sanitize_notebook_html(image, &config), image);
+        assert_eq!(sanitize(image), "<img />");
+
+        for html in [
+            r#"<a href="data:image/png;base64,iVBORw0KGgo=">x</a>"#,
+            r#"<div src="data:image/png;base64,iVBORw0KGgo=">x</div>"#,
+            r#"<img src="data:text/html;base64,PHNjcmlwdD4=" />"#,
+            r#"<img src="data:image/svg+xml,<svg onload=alert(1)>" />"#,
+        ] {
+            let sanitized = sanitize_notebook_html(html, &config);
+            assert!(!sanitized.contains("data:"), "{}", sanitized);
+        }
+    }
+
+    #[test]
+    fn removes_comments_and_escapes_stray_brackets() {
+        assert_eq!(sanitize("a<!-- <script>alert(1)</script> -->b"), "ab");
+        assert_eq!(sanitize("1 < 2 <3"), "1 &lt; 2 &lt;3");
+        assert_eq!(sanitize(r#"<a title='"><script>'>x</a>"#), r#"<a title="&quot;&gt;&lt;script&gt;">x</a>"#);
+    }
+}
@@ -0,0 +1,579 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use crate::model::{JDCategory, Project};
+use crate::utils::format::format_date;
+use crate::utils::fuzzy::{Vocabulary, VocabularyBuilder};
+use crate::utils::markdown::{html_escape, markdown_to_plain_text};
+use crate::utils::search_query::{has_sequence, SearchQuery, Searchable};
+use crate::utils::site_config::get_site_config;
+
+// BM25 parameters: term frequency saturation and length normalisation
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// How much text to show before the first match in a snippet, in bytes
+const SNIPPET_CONTEXT: usize = 40;
+
+// Common words that carry no meaning in a query
+pub(crate) const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have",
+    "in", "is", "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were",
+    "will", "with",
+];
+
+// The parts of a post that are indexed separately, so each can be boosted
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Title,
+    Headings,
+    Tags,
+    Body,
+}
+
+const FIELDS: [Field; 4] = [Field::Title, Field::Headings, Field::Tags, Field::Body];
+
+// Words in a run of text, as (start, end) byte ranges and the lowercased word
+pub fn word_spans(text: &str) -> Vec<(usize, usize, String)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                spans.push((s, i, text[s..i].to_lowercase()));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, text.len(), text[s..].to_lowercase()));
+    }
+
+    spans
+}
+
+// The stemmed terms of a run of text, without stop words
+pub fn terms(text: &str) -> Vec<String> {
+    word_spans(text)
+        .into_iter()
+        .filter(|(_, _, word)| !STOP_WORDS.contains(&word.as_str()))
+        .map(|(_, _, word)| stem(&word))
+        .collect()
+}
+
+// Reduce an English word to its stem with the Porter algorithm, so "searching",
+// "searched" and "searches" all match "search". Expects a lowercase word;
+// anything that isn't plain ASCII letters is left as it is.
+pub fn stem(word: &str) -> String {
+    if word.len() <= 2 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return word.to_string();
+    }
+
+    let mut w = word.as_bytes().to_vec();
+    step_1ab(&mut w);
+
+    // Step 1c: y -> i after a vowel
+    if w.ends_with(b"y") && has_vowel(&w[..w.len() - 1]) {
+        let last = w.len() - 1;
+        w[last] = b'i';
+    }
+
+    replace_suffix(&mut w, 0, &[
+        ("ational", "ate"), ("tional", "tion"), ("enci", "ence"), ("anci", "ance"),
+        ("izer", "ize"), ("abli", "able"), ("alli", "al"), ("entli", "ent"), ("eli", "e"),
+        ("ousli", "ous"), ("ization", "ize"), ("ation", "ate"), ("ator", "ate"),
+        ("alism", "al"), ("iveness", "ive"), ("fulness", "ful"), ("ousness", "ous"),
+        ("aliti", "al"), ("iviti", "ive"), ("biliti", "ble"),
+    ]);
+    replace_suffix(&mut w, 0, &[
+        ("icate", "ic"), ("ative", ""), ("alize", "al"), ("iciti", "ic"), ("ical", "ic"),
+        ("ful", ""), ("ness", ""),
+    ]);
+    step_4(&mut w);
+
+    // Step 5: drop a final e, and -ll -> -l
+    if w.ends_with(b"e") {
+        let m = measure(&w[..w.len() - 1]);
+        if m > 1 || (m == 1 && !ends_cvc(&w[..w.len() - 1])) {
+            w.pop();
+        }
+    }
+    if w.ends_with(b"ll") && measure(&w) > 1 {
+        w.pop();
+    }
+
+    String::from_utf8(w).unwrap_or_else(|_| word.to_string())
+}
+
+// Plurals and -ed / -ing
+fn step_1ab(w: &mut Vec<u8>) {
+    if w.ends_with(b"sses") || w.ends_with(b"ies") {
+        w.truncate(w.len() - 2);
+    } else if w.ends_with(b"s") && !w.ends_with(b"ss") {
+        w.pop();
+    }
+
+    if w.ends_with(b"eed") {
+        if measure(&w[..w.len() - 3]) > 0 {
+            w.pop();
+        }
+        return;
+    }
+
+    let suffix_len = if w.ends_with(b"ed") {
+        2
+    } else if w.ends_with(b"ing") {
+        3
+    } else {
+        return;
+    };
+    if !has_vowel(&w[..w.len() - suffix_len]) {
+        return;
+    }
+    w.truncate(w.len() - suffix_len);
+
+    if w.ends_with(b"at") || w.ends_with(b"bl") || w.ends_with(b"iz") {
+        w.push(b'e');
+    } else if ends_double_consonant(w) && !matches!(w.last(), Some(b'l' | b's' | b'z')) {
+        w.pop();
+    } else if measure(w) == 1 && ends_cvc(w) {
+        w.push(b'e');
+    }
+}
+
+fn step_4(w: &mut Vec<u8>) {
+    const SUFFIXES: &[&str] = &[
+        "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent",
+        "ion", "ou", "ism", "ate", "iti", "ous", "ive", "ize",
+    ];
+
+    let Some(suffix) = SUFFIXES.iter().find(|s| w.ends_with(s.as_bytes())) else {
+        return;
+    };
+    let stem = &w[..w.len() - suffix.len()];
+
+    if *suffix == "ion" && !matches!(stem.last(), Some(b's' | b't')) {
+        return;
+    }
+    if measure(stem) > 1 {
+        w.truncate(stem.len());
+    }
+}
+
+// Replace the first matching suffix when what's left has a measure above `min_measure`
+fn replace_suffix(w: &mut Vec<u8>, min_measure: usize, rules: &[(&str, &str)]) {
+    let Some((suffix, replacement)) = rules.iter().find(|(s, _)| w.ends_with(s.as_bytes())) else {
+        return;
+    };
+    let stem_len = w.len() - suffix.len();
+
+    if measure(&w[..stem_len]) > min_measure {
+        w.truncate(stem_len);
+        w.extend_from_slice(replacement.as_bytes());
+    }
+}
+
+fn is_consonant(w: &[u8], i: usize) -> bool {
+    match w[i] {
+        b'a' | b'e' | b'i' | b'o' | b'u' => false,
+        b'y' => i == 0 || !is_consonant(w, i - 1),
+        _ => true,
+    }
+}
+
+// The number of vowel-consonant sequences in a stem
+fn measure(w: &[u8]) -> usize {
+    let mut m = 0;
+    let mut after_vowel = false;
+
+    for i in 0..w.len() {
+        let consonant = is_consonant(w, i);
+        if consonant && after_vowel {
+            m += 1;
+        }
+        after_vowel = !consonant;
+    }
+
+    m
+}
+
+fn has_vowel(w: &[u8]) -> bool {
+    (0..w.len()).any(|i| !is_consonant(w, i))
+}
+
+fn ends_double_consonant(w: &[u8]) -> bool {
+    let n = w.len();
+    n >= 2 && w[n - 1] == w[n - 2] && is_consonant(w, n - 1)
+}
+
+// Consonant-vowel-consonant, where the last consonant isn't w, x or y
+fn ends_cvc(w: &[u8]) -> bool {
+    let n = w.len();
+    n >= 3
+        && is_consonant(w, n - 3)
+        && !is_consonant(w, n - 2)
+        && is_consonant(w, n - 1)
+        && !matches!(w[n - 1], b'w' | b'x' | b'y')
+}
+
+// How often a term appears in each field of one post
+#[derive(Debug, Clone)]
+struct Posting {
+    doc: usize,
+    frequencies: [u32; 4],
+}
+
+// An inverted index from stemmed terms to the posts containing them.
+// Documents are numbered in the order of the projects the index was built from.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    postings: HashMap<String, Vec<Posting>>,
+    field_lengths: Vec<[u32; 4]>,
+    average_lengths: [f64; 4],
+    vocabulary: Vocabulary,
+}
+
+impl SearchIndex {
+    pub fn build(projects: &[Project]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        let mut vocabulary = VocabularyBuilder::default();
+
+        for (doc, project) in projects.iter().enumerate() {
+            let mut frequencies: HashMap<String, [u32; 4]> = HashMap::new();
+            let mut lengths = [0u32; 4];
+            let mut texts = Vec::with_capacity(FIELDS.len());
+
+            for (f, field) in FIELDS.iter().enumerate() {
+                let text = field_text(project, *field);
+                for term in terms(&text) {
+                    frequencies.entry(term).or_default()[f] += 1;
+                    lengths[f] += 1;
+                }
+                texts.push(text);
+            }
+            vocabulary.add_post(&texts.join("\n"), &project.tech_stack);
+
+            for (term, frequencies) in frequencies {
+                index.postings.entry(term).or_default().push(Posting { doc, frequencies });
+            }
+            index.field_lengths.push(lengths);
+        }
+
+        let docs = index.field_lengths.len().max(1) as f64;
+        for f in 0..FIELDS.len() {
+            let total: u32 = index.field_lengths.iter().map(|lengths| lengths[f]).sum();
+            index.average_lengths[f] = (total as f64 / docs).max(1.0);
+        }
+        index.vocabulary = vocabulary.build();
+
+        index
+    }
+
+    // The words and tags in the indexed posts, for typo tolerance
+    pub fn vocabulary(&self) -> &Vocabulary {
+        &self.vocabulary
+    }
+
+    // BM25F scores for the posts matching any of the query terms, best first.
+    // Each field's term frequency is normalised by its length, weighted by its
+    // boost, and the combined frequency is saturated once per term.
+    pub fn score(&self, query_terms: &[String]) -> Vec<(usize, f64)> {
+        let config = &get_site_config().search;
+        let boosts = [config.title_boost, config.heading_boost, config.tag_boost, config.body_boost];
+        let docs = self.field_lengths.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+
+        let mut seen = HashSet::new();
+        for term in query_terms.iter().filter(|term| seen.insert(term.as_str())) {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+
+            let matching = postings.len() as f64;
+            let idf = (1.0 + (docs - matching + 0.5) / (matching + 0.5)).ln();
+
+            for posting in postings {
+                let lengths = &self.field_lengths[posting.doc];
+                let frequency: f64 = (0..FIELDS.len())
+                    .map(|f| {
+                        let normalised = 1.0 - B + B * lengths[f] as f64 / self.average_lengths[f];
+                        boosts[f] * posting.frequencies[f] as f64 / normalised
+                    })
+                    .sum();
+
+                *scores.entry(posting.doc).or_default() += idf * frequency / (K1 + frequency);
+            }
+        }
+
+        let mut scores: Vec<(usize, f64)> = scores.into_iter().collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores
+    }
+}
+
+fn field_text(project: &Project, field: Field) -> String {
+    match field {
+        Field::Title => project.title.clone(),
+        Field::Headings => project.rendered.as_ref()
+            .map(|rendered| rendered.toc.iter().map(|entry| entry.text.as_str()).collect::<Vec<_>>().join("\n"))
+            .unwrap_or_default(),
+        Field::Tags => project.tech_stack.join(" "),
+        Field::Body => body_text(project),
+    }
+}
+
+fn body_text(project: &Project) -> String {
+    match &project.rendered {
+        Some(rendered) => rendered.plain_text.clone(),
+        None => markdown_to_plain_text(&project.content),
+    }
+}
+
+// The index for the current content, rebuilt only when a post's indexed text changes
+static INDEX: Mutex<Option<(u64, Arc<SearchIndex>)>> = Mutex::new(None);
+
+pub fn search_index(projects: &[Project]) -> Arc<SearchIndex> {
+    let mut hasher = DefaultHasher::new();
+    for project in projects {
+        project.slug.hash(&mut hasher);
+        for field in FIELDS {
+            field_text(project, field).hash(&mut hasher);
+        }
+    }
+    let hash = hasher.finish();
+
+    let mut cached = INDEX.lock().unwrap();
+    if let Some((cached_hash, index)) = cached.as_ref() {
+        if *cached_hash == hash {
+            return index.clone();
+        }
+    }
+
+    let index = Arc::new(SearchIndex::build(projects));
+    *cached = Some((hash, index.clone()));
+    index
+}
+
+// A ranked result, with the query's terms highlighted in `<mark>` in the
+// title and in a snippet of the body
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchHit {
+    pub slug: String,
+    pub url: String,
+    pub title: String,
+    pub title_html: String,
+    pub snippet_html: String,
+    pub jd_identifier: String,
+    pub tags: Vec<String>,
+    // Publication date, YYYY-MM-DD
+    pub published: String,
+    pub score: f64,
+}
+
+// A post as the query language sees it on the server, looking terms up in the index
+struct IndexedProject<'a> {
+    project: &'a Project,
+    index: &'a SearchIndex,
+    doc: usize,
+}
+
+impl Searchable for IndexedProject<'_> {
+    fn tags(&self) -> &[String] {
+        &self.project.tech_stack
+    }
+
+    fn category(&self) -> Option<&JDCategory> {
+        self.project.jd_category.as_ref()
+    }
+
+    fn published(&self) -> String {
+        format_date(self.project.created_at)
+    }
+
+    // Postings are in document order
+    fn has_term(&self, term: &str) -> bool {
+        self.index.postings.get(term)
+            .is_some_and(|postings| postings.binary_search_by_key(&self.doc, |posting| posting.doc).is_ok())
+    }
+
+    fn contains_phrase(&self, phrase: &[String]) -> bool {
+        FIELDS.iter().any(|field| has_sequence(&terms(&field_text(self.project, *field)), phrase))
+    }
+}
+
+// The best `limit` posts for a query, most relevant first. A query with
+// only filters, like `tag:rust`, lists every matching post, newest first.
+// `index` must have been built from `projects`, see `search_index`.
+pub fn search(projects: &[Project], index: &SearchIndex, query: &SearchQuery, limit: usize) -> Vec<SearchHit> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let query_terms = query.ranking_terms();
+
+    let candidates: Vec<(usize, f64)> = if query_terms.is_empty() {
+        let mut docs: Vec<usize> = (0..projects.len()).collect();
+        docs.sort_by_key(|&doc| Reverse(projects[doc].created_at));
+        docs.into_iter().map(|doc| (doc, 0.0)).collect()
+    } else {
+        index.score(&query_terms)
+    };
+
+    candidates.into_iter()
+        .filter_map(|(doc, score)| Some((projects.get(doc)?, doc, score)))
+        .filter(|(project, doc, _)| query.matches(&IndexedProject { project, index, doc: *doc }))
+        .take(limit)
+        .map(|(project, _, score)| SearchHit {
+            slug: project.slug.clone(),
+            url: format!("/projects/{}", project.slug),
+            title: project.title.clone(),
+            title_html: highlight(&project.title, &query_terms, usize::MAX),
+            snippet_html: highlight(&body_text(project), &query_terms, get_site_config().search.snippet_length),
+            jd_identifier: project.jd_identifier.clone(),
+            tags: project.tech_stack.clone(),
+            published: format_date(project.created_at),
+            score,
+        })
+        .collect()
+}
+
+// Escaped HTML for up to `max_len` bytes of `text`, with words matching the
+// query terms wrapped in `<mark>`. The excerpt starts just before the part
+// of the text with the most matches, and is cut at word boundaries.
+pub fn highlight(text: &str, query_terms: &[String], max_len: usize) -> String {
+    let spans = word_spans(text);
+    if spans.is_empty() {
+        return html_escape(text);
+    }
+
+    let matched: Vec<bool> = spans.iter().map(|(_, _, word)| query_terms.contains(&stem(word))).collect();
+
+    // The match followed by the most other matches within the excerpt
+    let best = (0..spans.len())
+        .filter(|&i| matched[i])
+        .max_by_key(|&i| {
+            let count = (i..spans.len())
+                .take_while(|&j| spans[j].1 - spans[i].0 <= max_len)
+                .filter(|&j| matched[j])
+                .count();
+            (count, std::cmp::Reverse(i))
+        })
+        .unwrap_or(0);
+
+    let mut first = best;
+    while first > 0 && spans[best].0 - spans[first - 1].0 <= SNIPPET_CONTEXT {
+        first -= 1;
+    }
+    let mut last = first;
+    while last + 1 < spans.len() && spans[last + 1].1 - spans[first].0 <= max_len {
+        last += 1;
+    }
+
+    let start = if first == 0 { 0 } else { spans[first].0 };
+    let end = if last + 1 == spans.len() { text.len() } else { spans[last].1 };
+
+    let mut html = String::new();
+    if start > 0 {
+        html.push('…');
+    }
+
+    let mut position = start;
+    for i in first..=last {
+        let (span_start, span_end, _) = spans[i];
+        if !matched[i] {
+            continue;
+        }
+        html.push_str(&html_escape(&text[position..span_start]));
+        html.push_str("<mark>");
+        html.push_str(&html_escape(&text[span_start..span_end]));
+        html.push_str("</mark>");
+        position = span_end;
+    }
+    html.push_str(&html_escape(&text[position..end]));
+
+    if end < text.len() {
+        html.push('…');
+    }
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+    use super::*;
+
+    fn project(slug: &str, title: &str, content: &str) -> Project {
+        Project {
+            id: None,
+            title: title.to_string(),
+            slug: slug.to_string(),
+            summary: String::new(),
+            content: content.to_string(),
+            tech_stack: Vec::new(),
+            repo_url: None,
+            live_url: None,
+            thumbnail: None,
+            created_at: SystemTime::UNIX_EPOCH,
+            updated_at: SystemTime::UNIX_EPOCH,
+            jd_category: None,
+            original_path: String::new(),
+            jd_identifier: String::new(),
+            related_articles: Vec::new(),
+            allow_html: false,
+            rendered: None,
+        }
+    }
+
+    #[test]
+    fn stems_follow_porter() {
+        let cases = [
+            ("caresses", "caress"), ("ponies", "poni"), ("cats", "cat"), ("agreed", "agre"),
+            ("hopping", "hop"), ("filing", "file"), ("happy", "happi"), ("relational", "relat"),
+            ("generalization", "gener"), ("searching", "search"), ("searched", "search"),
+            ("controll", "control"), ("rust", "rust"), ("über", "über"),
+        ];
+
+        for (word, expected) in cases {
+            assert_eq!(stem(word), expected, "{}", word);
+        }
+        assert_eq!(terms("The Searches of a parser"), vec!["search", "parser"]);
+    }
+
+    #[test]
+    fn title_matches_outrank_body_matches() {
+        let projects = vec![
+            project("body", "Notes on tooling", "Some words about macros and other things."),
+            project("title", "Macros", "Some words about other things."),
+            project("neither", "Unrelated", "Nothing to see here."),
+        ];
+        let index = SearchIndex::build(&projects);
+
+        let scores = index.score(&terms("macros"));
+        let ranked: Vec<&str> = scores.iter().map(|(doc, _)| projects[*doc].slug.as_str()).collect();
+        assert_eq!(ranked, vec!["title", "body"]);
+        assert!(scores[0].1 > scores[1].1);
+
+        // Repeating a query term doesn't count it twice
+        assert_eq!(index.score(&terms("macros macros")), scores);
+    }
+
+    #[test]
+    fn highlight_marks_and_trims() {
+        let query = terms("parsing");
+
+        assert_eq!(highlight("A <parsed> parses", &query, usize::MAX), "A &lt;<mark>parsed</mark>&gt; <mark>parses</mark>");
+        assert_eq!(highlight("No match here", &query, usize::MAX), "No match here");
+
+        let text = format!("{} the parsing is here {}", "filler ".repeat(20), "tail ".repeat(20));
+        let snippet = highlight(&text, &query, 80);
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'), "{}", snippet);
+        assert!(snippet.contains("<mark>parsing</mark>"), "{}", snippet);
+    }
+}
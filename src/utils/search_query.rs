@@ -0,0 +1,430 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use crate::model::JDCategory;
+use crate::utils::fuzzy::Vocabulary;
+use crate::utils::search::{terms, word_spans};
+use crate::utils::tags::tag_matches;
+
+// Shortest partly typed word that's completed to longer words
+const MIN_PREFIX: usize = 2;
+
+// What the query language needs to know about a post, so the same query
+// runs against full projects on the server and the compact index in the browser
+pub trait Searchable {
+    fn tags(&self) -> &[String];
+    fn category(&self) -> Option<&JDCategory>;
+    // YYYY-MM-DD
+    fn published(&self) -> String;
+    fn has_term(&self, term: &str) -> bool;
+    // Whether the stemmed terms of a phrase appear in the post, in order
+    fn contains_phrase(&self, phrase: &[String]) -> bool;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Word(String),
+    Phrase(String),
+    Tag(String),
+    Area(u8),
+    Category(u8),
+    // Dates are normalised to YYYY-MM-DD
+    After(String),
+    Before(String),
+}
+
+impl Filter {
+    fn matches(&self, post: &impl Searchable) -> bool {
+        match self {
+            Filter::Word(word) => terms(word).iter().all(|term| post.has_term(term)),
+            Filter::Phrase(phrase) => post.contains_phrase(&terms(phrase)),
+            Filter::Tag(tag) => post.tags().iter().any(|t| tag_matches(t, tag)),
+            Filter::Area(area) => post.category().is_some_and(|c| c.area_id == *area),
+            Filter::Category(id) => post.category().is_some_and(|c| c.id == *id),
+            Filter::After(date) => post.published() >= *date,
+            Filter::Before(date) => post.published() < *date,
+        }
+    }
+}
+
+// One part of a query, with where it came from so it can be removed again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub filter: Filter,
+    // Written with a leading `-`, to exclude matching posts
+    pub negated: bool,
+    // The clause as typed, e.g. `tag:rust`, and its byte range in the query
+    pub source: String,
+    pub span: (usize, usize),
+    // Other terms (for words) or tags that also match, from `SearchQuery::expand`
+    pub alternatives: Vec<String>,
+    // What the clause probably should have been, when it looks misspelt
+    pub correction: Option<String>,
+}
+
+impl Clause {
+    fn matches(&self, post: &impl Searchable) -> bool {
+        self.filter.matches(post) || self.alternatives.iter().any(|alternative| match self.filter {
+            Filter::Tag(_) => post.tags().iter().any(|t| tag_matches(t, alternative)),
+            _ => post.has_term(alternative),
+        })
+    }
+}
+
+// Part of a query that couldn't be understood, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryError {
+    pub source: String,
+    pub message: String,
+}
+
+// A search box query:
+//
+//     tag:rust area:10 cat:12 after:2025-01 "exact phrase" -draft
+//
+// Every clause must match. Parsing never fails outright; clauses that
+// can't be understood are left out and reported in `errors`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SearchQuery {
+    pub input: String,
+    pub clauses: Vec<Clause>,
+    pub errors: Vec<QueryError>,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> SearchQuery {
+        let mut query = SearchQuery { input: input.to_string(), ..SearchQuery::default() };
+
+        for (start, end, unterminated) in query_tokens(input) {
+            let source = &input[start..end];
+            let error = |message: String| QueryError { source: source.to_string(), message };
+
+            if unterminated {
+                query.errors.push(error("missing closing quote".to_string()));
+            }
+
+            match parse_filter(source) {
+                Ok(Some((filter, negated))) => query.clauses.push(Clause {
+                    filter,
+                    negated,
+                    source: source.to_string(),
+                    span: (start, end),
+                    alternatives: Vec::new(),
+                    correction: None,
+                }),
+                Ok(None) => {}
+                Err(message) => query.errors.push(error(message)),
+            }
+        }
+
+        query
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    pub fn matches(&self, post: &impl Searchable) -> bool {
+        self.clauses.iter().all(|clause| clause.matches(post) != clause.negated)
+    }
+
+    // Stemmed terms that should raise a post's relevance: the words and
+    // phrases being looked for and their alternatives, but not excluded ones
+    pub fn ranking_terms(&self) -> Vec<String> {
+        self.clauses.iter()
+            .filter(|clause| !clause.negated)
+            .flat_map(|clause| match &clause.filter {
+                Filter::Word(text) => terms(text).into_iter().chain(clause.alternatives.clone()).collect(),
+                Filter::Phrase(text) => terms(text),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    // Let words and tags match what the posts actually contain. The word
+    // being typed (the last one, with no space after it yet) also matches
+    // the words it could become, and words and tags no post has match the
+    // closest ones that are, with those suggested as corrections. Excluded
+    // words and tags are left exact.
+    pub fn expand(&mut self, vocabulary: &Vocabulary) {
+        let typing_at = (!self.input.ends_with(char::is_whitespace)).then_some(self.input.len());
+
+        for clause in self.clauses.iter_mut().filter(|clause| !clause.negated) {
+            match &clause.filter {
+                Filter::Word(word) => {
+                    let spans = word_spans(word);
+                    let [(_, _, lowercase)] = spans.as_slice() else {
+                        continue;
+                    };
+
+                    if typing_at == Some(clause.span.1) && lowercase.chars().count() >= MIN_PREFIX {
+                        clause.alternatives = vocabulary.completions(lowercase);
+                    }
+
+                    let term = &terms(word)[0];
+                    if clause.alternatives.is_empty() && !vocabulary.contains(term) {
+                        clause.alternatives = vocabulary.similar(term);
+                        clause.correction = clause.alternatives.first()
+                            .map(|closest| vocabulary.spelling(closest).to_string());
+                    }
+                }
+                Filter::Tag(tag) if !vocabulary.tags.iter().any(|t| tag_matches(t, tag)) => {
+                    clause.alternatives = vocabulary.similar_tags(tag);
+                    let key = clause.source.split(':').next().unwrap_or("tag");
+                    clause.correction = clause.alternatives.first()
+                        .map(|closest| format!("{}:{}", key, quote(closest)));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // The query with its misspelt words and tags corrected, if any are
+    pub fn suggestion(&self) -> Option<String> {
+        let mut suggestion = self.input.clone();
+        let mut corrected = false;
+
+        for clause in self.clauses.iter().rev() {
+            if let Some(correction) = &clause.correction {
+                suggestion.replace_range(clause.span.0..clause.span.1, correction);
+                corrected = true;
+            }
+        }
+
+        corrected.then_some(suggestion)
+    }
+
+    // Everything but plain words, which stay in the search box: the
+    // clauses shown as removable filters
+    pub fn filters(&self) -> impl Iterator<Item = (usize, &Clause)> {
+        self.clauses.iter()
+            .enumerate()
+            .filter(|(_, clause)| clause.negated || !matches!(clause.filter, Filter::Word(_)))
+    }
+
+    // The query as typed, without the clause at `index`
+    pub fn without(&self, index: usize) -> String {
+        let Some(clause) = self.clauses.get(index) else {
+            return self.input.clone();
+        };
+        let (start, end) = clause.span;
+
+        format!("{} {}", self.input[..start].trim_end(), self.input[end..].trim_start())
+            .trim()
+            .to_string()
+    }
+}
+
+// Split a query at whitespace outside double quotes. Returns each token's
+// byte range and whether it ends inside an unclosed quote.
+fn query_tokens(input: &str) -> Vec<(usize, usize, bool)> {
+    let mut tokens = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut in_quote = false;
+
+    for (i, c) in input.char_indices() {
+        if c.is_whitespace() && !in_quote {
+            if let Some(s) = start.take() {
+                tokens.push((s, i, false));
+            }
+            continue;
+        }
+        if start.is_none() {
+            start = Some(i);
+        }
+        if c == '"' {
+            in_quote = !in_quote;
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((s, input.len(), in_quote));
+    }
+
+    tokens
+}
+
+// The filter a token stands for, and whether it's negated. Words with
+// nothing searchable in them, like "the" or "-", are skipped.
+fn parse_filter(token: &str) -> Result<Option<(Filter, bool)>, String> {
+    let (negated, body) = match token.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token),
+    };
+
+    if body.starts_with('"') {
+        let phrase = unquote(body);
+        return Ok((!terms(phrase).is_empty()).then(|| (Filter::Phrase(phrase.to_string()), negated)));
+    }
+
+    let field = body.split_once(':')
+        .filter(|(key, _)| !key.is_empty() && key.chars().all(|c| c.is_ascii_alphabetic()));
+
+    let Some((key, value)) = field else {
+        return Ok((!terms(body).is_empty()).then(|| (Filter::Word(body.to_string()), negated)));
+    };
+
+    let value = unquote(value);
+    if value.is_empty() {
+        return Err(format!("`{}:` needs a value", key));
+    }
+
+    let filter = match key.to_lowercase().as_str() {
+        "tag" => Filter::Tag(value.to_string()),
+        "area" => {
+            let area: u8 = value.parse()
+                .map_err(|_| format!("`area:` expects an area number like 10, found `{}`", value))?;
+            if area % 10 != 0 {
+                return Err(format!("areas are numbered 10, 20, 30 and so on; did you mean `cat:{}`?", area));
+            }
+            Filter::Area(area)
+        }
+        "cat" | "category" => Filter::Category(value.parse()
+            .map_err(|_| format!("`{}:` expects a category number like 11, found `{}`", key, value))?),
+        "after" => Filter::After(parse_date(key, value)?),
+        "before" => Filter::Before(parse_date(key, value)?),
+        other => return Err(format!(
+            "unknown filter `{}:`; try tag:, area:, cat:, after: or before:", other
+        )),
+    };
+
+    Ok(Some((filter, negated)))
+}
+
+// The first day of a year (2025), month (2025-01) or a full date, as YYYY-MM-DD
+fn parse_date(key: &str, value: &str) -> Result<String, String> {
+    let parts: Vec<&str> = value.split('-').collect();
+    let number = |i: usize| parts.get(i).map_or(Some(1), |part| part.parse::<u32>().ok());
+
+    let date = match (parts.len(), parts[0].parse::<i32>().ok(), number(1), number(2)) {
+        (1..=3, Some(year), Some(month), Some(day)) => NaiveDate::from_ymd_opt(year, month, day),
+        _ => None,
+    };
+
+    date.map(|date| date.format("%Y-%m-%d").to_string())
+        .ok_or_else(|| format!("`{}:` expects a date like 2025, 2025-01 or 2025-01-31, found `{}`", key, value))
+}
+
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.strip_prefix('"').unwrap_or(value);
+    value.strip_suffix('"').unwrap_or(value)
+}
+
+// Whether `needle` appears as a run of consecutive terms in `haystack`
+pub fn has_sequence(haystack: &[String], needle: &[String]) -> bool {
+    needle.is_empty() || haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::fuzzy::VocabularyBuilder;
+    use super::*;
+
+    fn filters(input: &str) -> Vec<(Filter, bool)> {
+        SearchQuery::parse(input).clauses.into_iter().map(|clause| (clause.filter, clause.negated)).collect()
+    }
+
+    fn errors(query: &SearchQuery) -> Vec<&str> {
+        query.errors.iter().map(|error| error.message.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_words_phrases_and_filters() {
+        assert_eq!(filters("rust \"error handling\" tag:\"machine learning\" -draft -tag:old"), vec![
+            (Filter::Word("rust".to_string()), false),
+            (Filter::Phrase("error handling".to_string()), false),
+            (Filter::Tag("machine learning".to_string()), false),
+            (Filter::Word("draft".to_string()), true),
+            (Filter::Tag("old".to_string()), true),
+        ]);
+
+        assert_eq!(filters("area:20 cat:21 CATEGORY:5"), vec![
+            (Filter::Area(20), false),
+            (Filter::Category(21), false),
+            (Filter::Category(5), false),
+        ]);
+
+        // Stop words and lone dashes have nothing to search for
+        assert!(SearchQuery::parse("the - \"of a\"").is_empty());
+    }
+
+    #[test]
+    fn partial_dates_start_at_the_first_day() {
+        assert_eq!(filters("after:2025 before:2025-03 after:2024-02-29"), vec![
+            (Filter::After("2025-01-01".to_string()), false),
+            (Filter::Before("2025-03-01".to_string()), false),
+            (Filter::After("2024-02-29".to_string()), false),
+        ]);
+    }
+
+    #[test]
+    fn reports_each_error() {
+        let date = "`after:` expects a date like 2025, 2025-01 or 2025-01-31, found";
+        let cases = [
+            ("tag:", "`tag:` needs a value"),
+            ("area:ten", "`area:` expects an area number like 10, found `ten`"),
+            ("area:300", "`area:` expects an area number like 10, found `300`"),
+            ("area:12", "areas are numbered 10, 20, 30 and so on; did you mean `cat:12`?"),
+            ("cat:x", "`cat:` expects a category number like 11, found `x`"),
+            ("category:1.5", "`category:` expects a category number like 11, found `1.5`"),
+            ("after:2025-13", &format!("{} `2025-13`", date)),
+            ("after:2023-02-29", &format!("{} `2023-02-29`", date)),
+            ("after:2025-01-01-01", &format!("{} `2025-01-01-01`", date)),
+            ("author:me", "unknown filter `author:`; try tag:, area:, cat:, after: or before:"),
+        ];
+
+        for (input, message) in cases {
+            let query = SearchQuery::parse(&format!("rust {}", input));
+            assert_eq!(errors(&query), vec![message], "{}", input);
+            // The rest of the query still works
+            assert_eq!(query.clauses.len(), 1, "{}", input);
+            assert_eq!(query.errors[0].source, input);
+        }
+    }
+
+    #[test]
+    fn unterminated_quotes_run_to_the_end() {
+        let query = SearchQuery::parse("rust \"error handling");
+
+        assert_eq!(errors(&query), vec!["missing closing quote"]);
+        assert_eq!(query.errors[0].source, "\"error handling");
+        assert_eq!(query.clauses[1].filter, Filter::Phrase("error handling".to_string()));
+    }
+
+    #[test]
+    fn without_removes_one_clause() {
+        let query = SearchQuery::parse("  rust  tag:\"machine learning\"   -draft ");
+
+        assert_eq!(query.clauses[1].span, (8, 30));
+        assert_eq!(query.without(0), "tag:\"machine learning\"   -draft");
+        assert_eq!(query.without(1), "rust -draft");
+        assert_eq!(query.without(2), "rust  tag:\"machine learning\"");
+        assert_eq!(query.without(3), query.input);
+    }
+
+    #[test]
+    fn suggestion_replaces_misspelt_clauses() {
+        let mut vocabulary = VocabularyBuilder::default();
+        vocabulary.add_post("Writing macros for parsing", &["rust".to_string()]);
+        let vocabulary = vocabulary.build();
+
+        let mut query = SearchQuery::parse("tag:rusty macrs -tag:rusty parsing ");
+        query.expand(&vocabulary);
+
+        assert_eq!(query.clauses[0].alternatives, vec!["rust".to_string()]);
+        assert_eq!(query.clauses[1].alternatives, vec!["macro".to_string()]);
+        // Excluded clauses are left alone
+        assert!(query.clauses[2].correction.is_none());
+        assert_eq!(query.suggestion().as_deref(), Some("tag:rust macros -tag:rusty parsing "));
+
+        let mut query = SearchQuery::parse("macros parsing");
+        query.expand(&vocabulary);
+        assert_eq!(query.suggestion(), None);
+    }
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use crate::model::{JDArea, JDCategory, Project};
+use crate::utils::site_config::get_site_config;
+
+// Absolute URL for a root-relative path; absolute URLs are returned as they are
+pub fn absolute_url(path: &str) -> String {
+    join_url(&get_site_config().site.base_url, path)
+}
+
+// Exactly one slash between the base URL and the path, however either is written
+fn join_url(base_url: &str, path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return path.to_string();
+    }
+
+    format!("{}/{}", base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+}
+
+// The Johnny Decimal trail to a page, as (name, path) pairs, matching the
+// breadcrumbs the pages render: Areas > area > category > post
+pub fn breadcrumb_trail(area: Option<&JDArea>, category: Option<&JDCategory>, post: Option<&Project>) -> Vec<(String, String)> {
+    let mut trail = vec![("Areas".to_string(), "/areas".to_string())];
+
+    if let Some(area) = area {
+        trail.push((area.name.clone(), format!("/areas/{}", area.id)));
+    }
+    if let Some(category) = category {
+        trail.push((category.name.clone(), format!("/categories/{}", category.id)));
+    }
+    if let Some(post) = post {
+        trail.push((post.title.clone(), format!("/projects/{}", post.slug)));
+    }
+
+    trail
+}
+
+// schema.org BreadcrumbList for a breadcrumb trail
+pub fn breadcrumb_json_ld(trail: &[(String, String)]) -> Value {
+    let items: Vec<Value> = trail.iter()
+        .enumerate()
+        .map(|(i, (name, path))| json!({
+            "@type": "ListItem",
+            "position": i + 1,
+            "name": name,
+            "item": absolute_url(path),
+        }))
+        .collect();
+
+    json!({
+        "@context": "https://schema.org",
+        "@type": "BreadcrumbList",
+        "itemListElement": items,
+    })
+}
+
+// schema.org BlogPosting for a post
+pub fn blog_posting_json_ld(project: &Project, image: Option<&str>) -> Value {
+    let site = &get_site_config().site;
+    let url = absolute_url(&format!("/projects/{}", project.slug));
+
+    let mut posting = json!({
+        "@context": "https://schema.org",
+        "@type": "BlogPosting",
+        "headline": project.title,
+        "description": project.summary,
+        "url": url,
+        "mainEntityOfPage": { "@type": "WebPage", "@id": url },
+        "datePublished": DateTime::<Utc>::from(project.created_at).to_rfc3339(),
+        "dateModified": DateTime::<Utc>::from(project.updated_at).to_rfc3339(),
+        "author": { "@type": "Person", "name": site.author },
+        "publisher": { "@type": "Person", "name": site.author },
+        "keywords": project.tech_stack.join(", "),
+    });
+
+    if !project.jd_identifier.is_empty() {
+        posting["identifier"] = json!(project.jd_identifier);
+    }
+    if let Some(category) = &project.jd_category {
+        posting["articleSection"] = json!(category.name);
+    }
+    if let Some(image) = image {
+        posting["image"] = json!(absolute_url(image));
+    }
+
+    posting
+}
+
+// JSON-LD blocks as the contents of a `<script type="application/ld+json">`.
+// `<` is escaped so a title containing `</script>` can't end the script early.
+pub fn json_ld_script(data: &[Value]) -> String {
+    let json = match data {
+        [single] => single.to_string(),
+        _ => Value::Array(data.to_vec()).to_string(),
+    };
+
+    json.replace('<', "\\u003c")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_urls_with_one_slash() {
+        assert_eq!(join_url("https://site.dev", "/posts/x"), "https://site.dev/posts/x");
+        assert_eq!(join_url("https://site.dev", "posts/x"), "https://site.dev/posts/x");
+        assert_eq!(join_url("https://site.dev/", "/posts/x"), "https://site.dev/posts/x");
+        assert_eq!(join_url("https://site.dev", ""), "https://site.dev/");
+        assert_eq!(join_url("https://site.dev", "https://cdn.dev/a.png"), "https://cdn.dev/a.png");
+    }
+}
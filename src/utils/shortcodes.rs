@@ -0,0 +1,393 @@
+use std::sync::Mutex;
+use leptos::prelude::{AnyView, RenderHtml};
+use pulldown_cmark::{Event, Parser, Tag};
+use crate::utils::markdown::{html_escape, markdown_options};
+use crate::utils::transforms::{merge_text_events, RenderContext};
+
+// A parsed `{{< name positional key="value" >}}` shortcode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortcode {
+    pub name: String,
+    pub positional: Vec<String>,
+    pub named: Vec<(String, String)>,
+}
+
+impl Shortcode {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.named.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn arg(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(|s| s.as_str())
+    }
+
+    // Named argument, falling back to a positional one
+    pub fn get_or_arg(&self, key: &str, index: usize) -> Option<&str> {
+        self.get(key).or_else(|| self.arg(index))
+    }
+}
+
+pub enum ShortcodeOutput {
+    Html(String),
+    View(AnyView),
+}
+
+impl ShortcodeOutput {
+    pub fn into_html(self) -> String {
+        match self {
+            ShortcodeOutput::Html(html) => html,
+            ShortcodeOutput::View(view) => view.to_html(),
+        }
+    }
+}
+
+pub type ShortcodeHandler = fn(&Shortcode, &RenderContext) -> Result<ShortcodeOutput, String>;
+
+static REGISTRY: Mutex<Vec<(String, ShortcodeHandler)>> = Mutex::new(Vec::new());
+
+fn builtin_shortcodes() -> Vec<(&'static str, ShortcodeHandler)> {
+    vec![
+        ("youtube", youtube),
+        ("figure", figure),
+        ("gist", gist),
+        ("jd", jd_link),
+    ]
+}
+
+// Register a custom shortcode handler. Registering an existing name replaces it.
+pub fn register_shortcode(name: &str, handler: ShortcodeHandler) {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.retain(|(existing, _)| existing != name);
+    registry.push((name.to_string(), handler));
+}
+
+pub fn find_shortcode_handler(name: &str) -> Option<ShortcodeHandler> {
+    let registry = REGISTRY.lock().unwrap();
+
+    registry.iter()
+        .find(|(registered, _)| registered == name)
+        .map(|(_, handler)| *handler)
+        .or_else(|| {
+            builtin_shortcodes().into_iter()
+                .find(|(builtin, _)| *builtin == name)
+                .map(|(_, handler)| handler)
+        })
+}
+
+pub fn render_shortcode(shortcode: &Shortcode, ctx: &RenderContext) -> Result<String, String> {
+    let handler = find_shortcode_handler(&shortcode.name)
+        .ok_or_else(|| format!("unknown shortcode `{}`", shortcode.name))?;
+
+    handler(shortcode, ctx).map(ShortcodeOutput::into_html)
+}
+
+// Find the next shortcode in `text`.
+// Returns the byte range it covers and the parsed shortcode.
+pub fn find_shortcode(text: &str) -> Option<(usize, usize, Shortcode)> {
+    let mut search_from = 0;
+
+    while let Some(start) = text[search_from..].find("{{<").map(|pos| pos + search_from) {
+        let inner_start = start + 3;
+        let end = text[inner_start..].find(">}}")? + inner_start;
+
+        if let Some(shortcode) = parse_shortcode(&text[inner_start..end]) {
+            return Some((start, end + 3, shortcode));
+        }
+
+        search_from = inner_start;
+    }
+
+    None
+}
+
+fn parse_shortcode(inner: &str) -> Option<Shortcode> {
+    let tokens = tokenize(inner.trim());
+    let mut tokens = tokens.into_iter();
+
+    let name = tokens.next()?;
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        return None;
+    }
+
+    let mut positional = Vec::new();
+    let mut named = Vec::new();
+
+    for token in tokens {
+        match token.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !key.contains('"') => {
+                named.push((key.to_string(), unquote(value).to_string()));
+            }
+            _ => positional.push(unquote(&token).to_string()),
+        }
+    }
+
+    Some(Shortcode { name, positional, named })
+}
+
+// Split on whitespace, keeping quoted sections together
+pub(crate) fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+
+    for c in input.chars() {
+        match quote {
+            Some(q) if c == q => {
+                quote = None;
+                current.push(c);
+            }
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            None if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            None => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+pub(crate) fn unquote(value: &str) -> &str {
+    if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\''))) {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+// Events with each shortcode in a single text run, so `find_shortcode` sees
+// it. pulldown-cmark reads the no-space form `{{<name>}}` as an inline HTML
+// tag between `{{` and `}}`, so HTML right between those is text again.
+// Code blocks are left alone; code spans are never text.
+pub fn join_shortcode_text(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut joined: Vec<Event> = Vec::with_capacity(events.len());
+    let mut in_code_block = false;
+    let mut events = events.into_iter().peekable();
+
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Html(html) if !in_code_block
+                && matches!(joined.last(), Some(Event::Text(text)) if text.ends_with("{{"))
+                && matches!(events.peek(), Some(Event::Text(text)) if text.starts_with("}}")) => {
+                joined.push(Event::Text(html));
+                continue;
+            }
+            _ => {}
+        }
+        joined.push(event);
+    }
+
+    merge_text_events(joined)
+}
+
+// Names of shortcodes used in `content` that have no handler, with their
+// line numbers. Shortcodes are found the same way the `shortcodes` transform
+// finds them, so ones in code blocks and code spans are ignored.
+pub fn find_unknown_shortcodes(content: &str) -> Vec<(usize, String)> {
+    let events = join_shortcode_text(Parser::new_ext(content, markdown_options()).collect());
+    let mut unknown = Vec::new();
+    let mut in_code_block = false;
+    // Just past the last shortcode found in the source, to number lines
+    let mut position = 0;
+
+    for event in events {
+        let text = match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                continue;
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code_block = false;
+                continue;
+            }
+            Event::Text(text) if !in_code_block => text,
+            _ => continue,
+        };
+
+        let mut rest = text.as_ref();
+        while let Some((start, end, shortcode)) = find_shortcode(rest) {
+            let line = match content[position..].find(&rest[start..end]) {
+                Some(found) => {
+                    let line = content[..position + found].matches('\n').count() + 1;
+                    position += found + end - start;
+                    line
+                }
+                None => content[..position].matches('\n').count() + 1,
+            };
+            if find_shortcode_handler(&shortcode.name).is_none() {
+                unknown.push((line, shortcode.name));
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    unknown
+}
+
+// Where embed placeholders may load an iframe from: the embed shortcodes'
+// own URLs. Checked by the sanitiser and again by `EmbedLoader`, so raw HTML
+// in a post can't turn a placeholder into an iframe of any other site.
+const EMBED_URL_PREFIXES: &[&str] = &[
+    "https://www.youtube-nocookie.com/embed/",
+    "https://gist.github.com/",
+];
+
+pub fn is_embed_url(src: &str) -> bool {
+    EMBED_URL_PREFIXES.iter().any(|prefix| {
+        src.strip_prefix(prefix).is_some_and(|path| !path.is_empty() && !path.contains(['\\', '@']))
+    })
+}
+
+// Third-party embeds render as a placeholder; the `EmbedLoader` island swaps
+// in the iframe when the reader clicks, so nothing is fetched before that.
+fn embed_placeholder(provider: &str, embed_src: &str, title: &str, fallback_url: &str, fallback_text: &str) -> String {
+    format!(
+        "<div class=\"embed-placeholder embed-{provider}\" data-embed-src=\"{src}\" data-embed-title=\"{title}\">\
+<p class=\"embed-notice\">{title}<br/>This embed loads content from {provider_name}, which may set cookies.</p>\
+<button type=\"button\" class=\"embed-load\">Load content</button> \
+<a href=\"{fallback}\" class=\"embed-fallback\" target=\"_blank\" rel=\"noopener noreferrer\">{fallback_text}</a>\
+</div>\n",
+        provider = html_escape(&provider.to_lowercase()),
+        provider_name = html_escape(provider),
+        src = html_escape(embed_src),
+        title = html_escape(title),
+        fallback = html_escape(fallback_url),
+        fallback_text = html_escape(fallback_text),
+    )
+}
+
+// {{< youtube id="dQw4w9WgXcQ" title="..." >}} or {{< youtube dQw4w9WgXcQ >}}
+fn youtube(shortcode: &Shortcode, _ctx: &RenderContext) -> Result<ShortcodeOutput, String> {
+    let id = shortcode.get_or_arg("id", 0)
+        .ok_or("youtube shortcode needs a video id")?;
+
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("invalid YouTube video id `{}`", id));
+    }
+
+    let title = shortcode.get("title").unwrap_or("YouTube video");
+    let start = shortcode.get("start")
+        .and_then(|s| s.parse::<u32>().ok())
+        .map(|s| format!("?start={}", s))
+        .unwrap_or_default();
+
+    Ok(ShortcodeOutput::Html(embed_placeholder(
+        "YouTube",
+        &format!("https://www.youtube-nocookie.com/embed/{}{}", id, start),
+        title,
+        &format!("https://www.youtube.com/watch?v={}", id),
+        "Watch on YouTube",
+    )))
+}
+
+// {{< gist user 0123abcd >}} or {{< gist user="user" id="0123abcd" >}}
+fn gist(shortcode: &Shortcode, _ctx: &RenderContext) -> Result<ShortcodeOutput, String> {
+    let user = shortcode.get_or_arg("user", 0)
+        .ok_or("gist shortcode needs a user")?;
+    let id = shortcode.get_or_arg("id", 1)
+        .ok_or("gist shortcode needs a gist id")?;
+
+    let valid = |s: &str| s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid(user) || !valid(id) {
+        return Err(format!("invalid gist reference `{}/{}`", user, id));
+    }
+
+    let title = shortcode.get("title").unwrap_or("GitHub Gist");
+
+    Ok(ShortcodeOutput::Html(embed_placeholder(
+        "GitHub",
+        &format!("https://gist.github.com/{}/{}.pibb", user, id),
+        title,
+        &format!("https://gist.github.com/{}/{}", user, id),
+        "View on GitHub",
+    )))
+}
+
+// {{< figure src="/images/chart.png" caption="..." alt="..." >}}
+fn figure(shortcode: &Shortcode, _ctx: &RenderContext) -> Result<ShortcodeOutput, String> {
+    let src = shortcode.get_or_arg("src", 0)
+        .ok_or("figure shortcode needs a src")?;
+    let caption = shortcode.get("caption");
+    let alt = shortcode.get("alt").or(caption).unwrap_or("");
+
+    let caption_html = caption
+        .map(|c| format!("<figcaption>{}</figcaption>", html_escape(c)))
+        .unwrap_or_default();
+
+    Ok(ShortcodeOutput::Html(format!(
+        "<figure class=\"figure\"><img src=\"{}\" alt=\"{}\" />{}</figure>\n",
+        html_escape(src),
+        html_escape(alt),
+        caption_html
+    )))
+}
+
+// {{< jd 41.01 >}} links to the post with that Johnny Decimal identifier
+fn jd_link(shortcode: &Shortcode, ctx: &RenderContext) -> Result<ShortcodeOutput, String> {
+    let jd_id = shortcode.get_or_arg("id", 0)
+        .ok_or("jd shortcode needs a Johnny Decimal identifier")?;
+
+    let project = ctx.projects.iter()
+        .find(|p| p.jd_identifier == jd_id)
+        .ok_or_else(|| format!("no post with Johnny Decimal identifier {}", jd_id))?;
+
+    Ok(ShortcodeOutput::Html(format!(
+        "<a href=\"/projects/{}\" class=\"jd-link\"><span class=\"jd-link-id\">{}</span> {}</a>",
+        html_escape(&project.slug),
+        html_escape(jd_id),
+        html_escape(&project.title)
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use pulldown_cmark::html;
+    use crate::utils::transforms::{MarkdownTransform, Shortcodes};
+    use super::*;
+
+    fn render(markdown: &str) -> String {
+        let events: Vec<Event> = Parser::new_ext(markdown, markdown_options()).collect();
+        let events = Shortcodes.transform(events, &mut RenderContext::new(&[]));
+        let mut html = String::new();
+        html::push_html(&mut html, events.into_iter());
+        html
+    }
+
+    #[test]
+    fn shortcodes_without_spaces_render() {
+        let html = render("{{<youtube dQw4w9WgXcQ>}}\n\nInline {{<youtube dQw4w9WgXcQ>}} too.\n");
+
+        assert_eq!(html.matches("data-embed-src=\"https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ\"").count(), 2, "{}", html);
+        assert!(!html.contains("{{"), "{}", html);
+    }
+
+    #[test]
+    fn unknown_shortcodes_are_found_outside_code() {
+        let content = "{{<nospace>}} and {{< spaced a=\"1\" >}}\n\n\
+            ```\n{{< fenced >}}\n```\n\n~~~~\n{{<tilde>}}\n```\n~~~~\n\n    {{< indented >}}\n\n\
+            Some `{{< inline >}}` code, {{< youtube abc >}} and {{<again>}}\n{{<again>}}\n";
+
+        assert_eq!(find_unknown_shortcodes(content), vec![
+            (1, "nospace".to_string()),
+            (1, "spaced".to_string()),
+            (14, "again".to_string()),
+            (15, "again".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,265 @@
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use leptos::logging::log;
+
+// Site-wide settings read from `site.json` at the project root.
+// Every field has a default, so the file (or any section of it) is optional.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct SiteConfig {
+    pub site: SiteInfo,
+    pub feeds: FeedConfig,
+    pub sitemap: SitemapConfig,
+    pub robots: RobotsConfig,
+    pub og_images: OgImageConfig,
+    pub search: SearchConfig,
+    pub sanitize: SanitizeConfig,
+    pub markdown: MarkdownConfig,
+    pub citations: CitationConfig,
+}
+
+// How the site describes itself to feed readers and other sites
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SiteInfo {
+    pub title: String,
+    pub description: String,
+    // Public URL of the site, without a trailing slash; used for absolute links
+    pub base_url: String,
+    pub author: String,
+}
+
+impl Default for SiteInfo {
+    fn default() -> Self {
+        SiteInfo {
+            title: "Tyler Harpool - Technology & Government Blog".to_string(),
+            description: "Tyler Harpool's personal blog and project showcase".to_string(),
+            base_url: "https://tylerharpool.fly.dev".to_string(),
+            author: "Tyler Harpool".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeedContent {
+    // The whole rendered post
+    #[default]
+    Full,
+    // Just the summary, with a link to the post
+    Summary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FeedConfig {
+    pub content: FeedContent,
+    // Number of most recent posts in each feed
+    pub limit: usize,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        FeedConfig {
+            content: FeedContent::default(),
+            limit: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SitemapConfig {
+    // Above this many URLs, /sitemap.xml becomes an index of smaller sitemaps
+    pub max_urls: usize,
+}
+
+impl Default for SitemapConfig {
+    fn default() -> Self {
+        // The limit search engines accept in one sitemap file
+        SitemapConfig { max_urls: 50_000 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RobotsConfig {
+    // Path prefixes crawlers should stay out of
+    pub disallow: Vec<String>,
+    // Extra lines appended to robots.txt as they are, e.g. rules for one crawler
+    pub extra: Vec<String>,
+}
+
+impl Default for RobotsConfig {
+    fn default() -> Self {
+        RobotsConfig {
+            disallow: vec!["/admin/".to_string(), "/preview/".to_string()],
+            extra: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct OgImageConfig {
+    // Where rendered social preview images are kept between requests
+    pub cache_dir: String,
+}
+
+impl Default for OgImageConfig {
+    fn default() -> Self {
+        OgImageConfig { cache_dir: "target/og-images".to_string() }
+    }
+}
+
+// Relevance weights and result sizes for full-text search
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SearchConfig {
+    // How much a match in each part of a post counts, relative to the body
+    pub title_boost: f64,
+    pub heading_boost: f64,
+    pub tag_boost: f64,
+    pub body_boost: f64,
+    // Longest snippet shown with each result, in bytes
+    pub snippet_length: usize,
+    // Most results returned by /api/search
+    pub limit: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            title_boost: 3.0,
+            heading_boost: 2.0,
+            tag_boost: 2.0,
+            body_boost: 1.0,
+            snippet_length: 160,
+            limit: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MarkdownConfig {
+    // Names of the transforms to run, in order (see `utils::transforms`)
+    pub transforms: Vec<String>,
+    // Where ```lang file=... code blocks read their snippets from
+    pub snippets_dir: String,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        MarkdownConfig {
+            transforms: ["shortcodes", "snippets", "data_blocks", "syntax_highlight", "post_queries", "wiki_links", "callouts", "citations", "inline_tags", "glossary", "heading_ids", "external_links", "sidenotes"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            snippets_dir: "content/snippets".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CitationStyle {
+    // [1], with a numbered bibliography in citation order
+    #[default]
+    Numeric,
+    // (Smith 2020), with the bibliography sorted by author
+    AuthorDate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CitationConfig {
+    // BibTeX (.bib) or CSL-JSON (.json) file that `[@key]` citations refer to
+    pub bibliography: String,
+    pub style: CitationStyle,
+}
+
+impl Default for CitationConfig {
+    fn default() -> Self {
+        CitationConfig {
+            bibliography: "content/references.bib".to_string(),
+            style: CitationStyle::default(),
+        }
+    }
+}
+
+// Allowlist applied to rendered markdown before it is injected with `inner_html`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SanitizeConfig {
+    pub enabled: bool,
+    // Lets a post opt out with `allow_html: true` in its front matter
+    pub allow_post_override: bool,
+    pub allowed_tags: Vec<String>,
+    // Attributes allowed on every allowed tag
+    pub allowed_attributes: Vec<String>,
+    pub allowed_url_schemes: Vec<String>,
+}
+
+impl Default for SanitizeConfig {
+    fn default() -> Self {
+        let tags = [
+            "a", "abbr", "b", "blockquote", "br", "caption", "code", "dd", "del", "details",
+            "div", "dl", "dt", "em", "figcaption", "figure", "h1", "h2", "h3", "h4", "h5", "h6",
+            "hr", "i", "img", "input", "kbd", "label", "li", "mark", "ol", "p", "pre", "s", "section",
+            "span", "strong", "sub", "summary", "sup", "table", "tbody", "td", "tfoot", "th",
+            "thead", "tr", "u", "ul", "button",
+        ];
+        let attributes = [
+            "href", "src", "alt", "title", "class", "id", "width", "height", "align",
+            "colspan", "rowspan", "start", "type", "checked", "disabled", "lang", "open",
+            "rel", "target", "data-embed-src", "data-embed-title", "for", "role", "aria-label",
+            "scope",
+        ];
+        let schemes = ["http", "https", "mailto"];
+
+        SanitizeConfig {
+            enabled: true,
+            allow_post_override: true,
+            allowed_tags: tags.iter().map(|s| s.to_string()).collect(),
+            allowed_attributes: attributes.iter().map(|s| s.to_string()).collect(),
+            allowed_url_schemes: schemes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+pub fn load_site_config(path: &str) -> SiteConfig {
+    let path = Path::new(path);
+
+    if !path.exists() {
+        log!("No site config found at {}, using defaults", path.display());
+        return SiteConfig::default();
+    }
+
+    match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str::<SiteConfig>(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                log!("Failed to parse site config {}: {}", path.display(), e);
+                SiteConfig::default()
+            }
+        },
+        Err(e) => {
+            log!("Failed to read site config {}: {}", path.display(), e);
+            SiteConfig::default()
+        }
+    }
+}
+
+// Caching: the config is read once and never changes, so it's handed out
+// by reference rather than cloned on every call
+static CONFIG: OnceLock<SiteConfig> = OnceLock::new();
+
+pub fn get_site_config() -> &'static SiteConfig {
+    CONFIG.get_or_init(|| {
+        let config_path = option_env!("SITE_CONFIG").unwrap_or("site.json");
+        load_site_config(config_path)
+    })
+}
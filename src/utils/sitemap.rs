@@ -0,0 +1,141 @@
+use std::time::SystemTime;
+use chrono::{DateTime, Utc};
+use crate::model::{get_all_areas, get_all_categories, Project};
+use crate::utils::feeds::encode_path_segment;
+use crate::utils::markdown::html_escape;
+use crate::utils::site_config::get_site_config;
+
+// A page listed in the sitemap, with a path relative to the site root
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    pub path: String,
+    pub lastmod: Option<SystemTime>,
+}
+
+// Every public page: the home, about, areas and glossary pages, then each
+// area, category, tag and post. Listing pages take the newest change of
+// the posts they show.
+pub fn sitemap_entries(projects: &[Project]) -> Vec<SitemapEntry> {
+    let mut entries = vec![
+        SitemapEntry { path: "/".to_string(), lastmod: newest(projects.iter()) },
+        SitemapEntry { path: "/about".to_string(), lastmod: None },
+        SitemapEntry { path: "/areas".to_string(), lastmod: newest(projects.iter()) },
+        SitemapEntry { path: "/glossary".to_string(), lastmod: None },
+    ];
+
+    for area in get_all_areas() {
+        let lastmod = newest(projects.iter()
+            .filter(|p| p.jd_category.as_ref().is_some_and(|c| c.area_id == area.id)));
+        entries.push(SitemapEntry { path: format!("/areas/{}", area.id), lastmod });
+    }
+
+    for category in get_all_categories() {
+        let lastmod = newest(projects.iter()
+            .filter(|p| p.jd_category.as_ref().is_some_and(|c| c.id == category.id)));
+        entries.push(SitemapEntry { path: format!("/categories/{}", category.id), lastmod });
+    }
+
+    let mut tags: Vec<&String> = Vec::new();
+    for tag in projects.iter().flat_map(|p| &p.tech_stack) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag);
+        }
+    }
+    tags.sort_by_key(|tag| tag.to_lowercase());
+
+    for tag in tags {
+        let lastmod = newest(projects.iter()
+            .filter(|p| p.tech_stack.iter().any(|t| t.eq_ignore_ascii_case(tag))));
+        entries.push(SitemapEntry { path: format!("/?tag={}", encode_path_segment(tag)), lastmod });
+    }
+
+    for project in projects {
+        entries.push(SitemapEntry { path: format!("/projects/{}", project.slug), lastmod: Some(project.updated_at) });
+    }
+
+    entries
+}
+
+fn newest<'a>(posts: impl Iterator<Item = &'a Project>) -> Option<SystemTime> {
+    posts.map(|p| p.updated_at).max()
+}
+
+// `/sitemap.xml`: all pages, or an index of `/sitemap/N.xml` files when there
+// are more than `sitemap.max_urls`
+pub fn sitemap_xml(projects: &[Project]) -> String {
+    let config = get_site_config();
+    let entries = sitemap_entries(projects);
+    let max_urls = config.sitemap.max_urls.max(1);
+
+    if entries.len() <= max_urls {
+        return urlset_xml(&entries, &config.site.base_url);
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+
+    for (i, chunk) in entries.chunks(max_urls).enumerate() {
+        xml.push_str("  <sitemap>\n");
+        xml.push_str(&format!("    <loc>{}/sitemap/{}.xml</loc>\n", html_escape(&config.site.base_url), i + 1));
+        if let Some(lastmod) = chunk.iter().filter_map(|e| e.lastmod).max() {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", w3c_date(lastmod)));
+        }
+        xml.push_str("  </sitemap>\n");
+    }
+
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+// One part of a split sitemap, numbered from 1
+pub fn sitemap_part_xml(projects: &[Project], part: usize) -> Option<String> {
+    let config = get_site_config();
+    let entries = sitemap_entries(projects);
+    let chunk = entries.chunks(config.sitemap.max_urls.max(1)).nth(part.checked_sub(1)?)?;
+
+    Some(urlset_xml(chunk, &config.site.base_url))
+}
+
+fn urlset_xml(entries: &[SitemapEntry], base_url: &str) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+
+    for entry in entries {
+        xml.push_str("  <url>\n");
+        xml.push_str(&format!("    <loc>{}{}</loc>\n", html_escape(base_url), html_escape(&entry.path)));
+        if let Some(lastmod) = entry.lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", w3c_date(lastmod)));
+        }
+        xml.push_str("  </url>\n");
+    }
+
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn w3c_date(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).format("%Y-%m-%d").to_string()
+}
+
+pub fn robots_txt() -> String {
+    let config = get_site_config();
+    let mut robots = String::from("User-agent: *\n");
+
+    if config.robots.disallow.is_empty() {
+        robots.push_str("Disallow:\n");
+    }
+    for path in &config.robots.disallow {
+        robots.push_str(&format!("Disallow: {}\n", path));
+    }
+
+    if !config.robots.extra.is_empty() {
+        robots.push('\n');
+    }
+    for line in &config.robots.extra {
+        robots.push_str(line);
+        robots.push('\n');
+    }
+
+    robots.push_str(&format!("\nSitemap: {}/sitemap.xml\n", config.site.base_url));
+    robots
+}
@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::{Component, Path};
+use crate::utils::site_config::get_site_config;
+
+// A code block that takes its code from a file in the snippets directory:
+//
+//     ```rust file=examples/server.rs lines=10-42
+//     ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetInclude {
+    pub language: String,
+    pub file: String,
+    // First and last line, 1-based and inclusive; `None` for the whole file
+    // or, for the end, the rest of it
+    pub lines: Option<(usize, Option<usize>)>,
+}
+
+impl SnippetInclude {
+    // Parse a fence info string. Returns `None` for ordinary code blocks
+    // (no `file=`), and an error when the include itself is malformed.
+    pub fn parse(info: &str) -> Option<Result<SnippetInclude, String>> {
+        let mut words = info.split_whitespace();
+        let first = words.next()?;
+
+        let (language, attributes): (&str, Vec<&str>) = if first.contains('=') {
+            ("", info.split_whitespace().collect())
+        } else {
+            (first, words.collect())
+        };
+
+        let file = attributes.iter().find_map(|a| a.strip_prefix("file="))?;
+        let lines = attributes.iter().find_map(|a| a.strip_prefix("lines="));
+
+        Some(parse_lines(lines).map(|lines| SnippetInclude {
+            language: language.to_string(),
+            file: file.trim_matches('"').to_string(),
+            lines,
+        }))
+    }
+
+    // The included code, dedented
+    pub fn read(&self) -> Result<String, String> {
+        self.read_from(&get_site_config().markdown.snippets_dir)
+    }
+
+    pub fn read_from(&self, snippets_dir: &str) -> Result<String, String> {
+        let relative = Path::new(&self.file);
+
+        // Keep includes inside the snippets directory
+        if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(format!("snippet path `{}` must be relative to {}", self.file, snippets_dir));
+        }
+
+        let path = Path::new(snippets_dir).join(relative);
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("can't read snippet {}: {}", path.display(), e))?;
+        let lines: Vec<&str> = source.lines().collect();
+
+        let (start, end) = match self.lines {
+            None => return Ok(dedent(&lines)),
+            Some((start, end)) => (start, end.unwrap_or(lines.len())),
+        };
+
+        if start == 0 || start > end || end > lines.len() {
+            return Err(format!(
+                "lines {}-{} are out of range for {} ({} lines)",
+                start, end, path.display(), lines.len()
+            ));
+        }
+
+        Ok(dedent(&lines[start - 1..end]))
+    }
+}
+
+// `10-42`, `10-` or `10`
+fn parse_lines(lines: Option<&str>) -> Result<Option<(usize, Option<usize>)>, String> {
+    let Some(lines) = lines else {
+        return Ok(None);
+    };
+    let invalid = || format!("invalid line range `{}`", lines);
+
+    match lines.split_once('-') {
+        Some((start, "")) => Ok(Some((start.parse().map_err(|_| invalid())?, None))),
+        Some((start, end)) => Ok(Some((
+            start.parse().map_err(|_| invalid())?,
+            Some(end.parse().map_err(|_| invalid())?),
+        ))),
+        None => {
+            let line = lines.parse().map_err(|_| invalid())?;
+            Ok(Some((line, Some(line))))
+        }
+    }
+}
+
+// Remove the indentation shared by every non-blank line, so a snippet
+// from the middle of a function doesn't start halfway across the block
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines.iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut code: String = lines.iter()
+        .map(|l| l.get(indent..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n");
+    code.push('\n');
+    code
+}
+
+// Every snippet include in a post's markdown, with its line number
+pub fn find_snippet_includes(content: &str) -> Vec<(usize, Result<SnippetInclude, String>)> {
+    let mut includes = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+
+        match fence {
+            Some(marker) => {
+                if trimmed.starts_with(marker) && trimmed.trim_start_matches(marker).trim().is_empty() {
+                    fence = None;
+                }
+            }
+            None => {
+                let marker = if trimmed.starts_with("```") {
+                    "```"
+                } else if trimmed.starts_with("~~~") {
+                    "~~~"
+                } else {
+                    continue;
+                };
+
+                fence = Some(marker);
+                if let Some(include) = SnippetInclude::parse(trimmed.trim_start_matches(marker)) {
+                    includes.push((i + 1, include));
+                }
+            }
+        }
+    }
+
+    includes
+}
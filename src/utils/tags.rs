@@ -0,0 +1,121 @@
+use std::sync::OnceLock;
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use crate::utils::markdown::markdown_options;
+use crate::utils::transforms::merge_text_events;
+
+// Find Obsidian-style inline tags (`#rust`, `#govtech/policy`) in a run of text.
+// Returns the byte range of each tag including the `#`, and the tag without it.
+// Tags must follow whitespace or an opening bracket and can't be all digits,
+// so "C#" and "#1" are left alone. Wiki links like `[[#Heading]]` are links,
+// not tags.
+pub fn find_inline_tags(text: &str) -> Vec<(usize, usize, String)> {
+    static TAG_RE: OnceLock<Regex> = OnceLock::new();
+    let tag_re = TAG_RE.get_or_init(|| Regex::new(r"(?:^|[\s(\[])#([\p{L}\p{N}_][\p{L}\p{N}_/\-]*)").unwrap());
+
+    let mut wiki_links = Vec::new();
+    let mut from = 0;
+    while let Some(start) = text[from..].find("[[").map(|start| from + start) {
+        let Some(end) = text[start..].find("]]").map(|end| start + end) else {
+            break;
+        };
+        wiki_links.push(start..end);
+        from = end;
+    }
+
+    tag_re.captures_iter(text)
+        .filter_map(|caps| {
+            let name = caps.get(1)?;
+            let tag = name.as_str().trim_end_matches(['/', '-']);
+
+            if tag.chars().all(|c| c.is_ascii_digit()) || wiki_links.iter().any(|link| link.contains(&name.start())) {
+                return None;
+            }
+
+            Some((name.start() - 1, name.start() + tag.len(), tag.to_string()))
+        })
+        .collect()
+}
+
+// All inline tags in a post's markdown, ignoring code, headings and link text.
+// The text is parsed and merged the same way as for the `InlineTags`
+// transform, so the post's tags match the ones linked in its body.
+pub fn extract_inline_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut skip_depth = 0;
+
+    for event in merge_text_events(Parser::new_ext(content, markdown_options()).collect()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Start(Tag::Heading(..)) | Event::Start(Tag::Link(..)) => skip_depth += 1,
+            Event::End(Tag::CodeBlock(_)) | Event::End(Tag::Heading(..)) | Event::End(Tag::Link(..)) => skip_depth -= 1,
+            Event::Text(text) if skip_depth == 0 => {
+                for (_, _, tag) in find_inline_tags(&text) {
+                    if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                        tags.push(tag);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tags
+}
+
+// Front matter tags first, then inline tags that aren't already present
+pub fn merge_tags(front_matter_tags: Vec<String>, inline_tags: Vec<String>) -> Vec<String> {
+    let mut merged = front_matter_tags;
+
+    for tag in inline_tags {
+        if !merged.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            merged.push(tag);
+        }
+    }
+
+    merged
+}
+
+// Whether `tag` matches a tag filter. Hierarchical tags match their parents,
+// so filtering by "govtech" also finds "govtech/policy".
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let filter = filter.to_lowercase();
+
+    tag == filter || tag.starts_with(&format!("{}/", filter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_tags_but_not_numbers_or_code() {
+        let content = "Notes on #rust and #govtech/policy, not C# or #1.\n\n\
+            `#inline` code, [#linked](/x) text and\n\n```\n#fenced\n```\n\n# Heading #heading\n";
+
+        assert_eq!(extract_inline_tags(content), ["rust", "govtech/policy"]);
+    }
+
+    #[test]
+    fn tags_split_across_text_events_stay_whole() {
+        // The parser splits text at `_` and `*`, which could start emphasis
+        let content = "Use #snake_case and #a*b names, and #tag_ again.";
+
+        assert_eq!(extract_inline_tags(content), ["snake_case", "a", "tag_"]);
+    }
+
+    #[test]
+    fn wiki_links_to_headings_are_not_tags() {
+        let content = "See [[#Setup]], [[Other Post#Heading]] and [[Note|the #note]], but [#bracketed] is a tag.";
+
+        assert_eq!(extract_inline_tags(content), ["bracketed"]);
+    }
+
+    #[test]
+    fn hierarchical_tags_match_their_parents() {
+        assert!(tag_matches("GovTech/Policy", "govtech"));
+        assert!(tag_matches("govtech", "GovTech"));
+        assert!(!tag_matches("govtechnology", "govtech"));
+        assert!(!tag_matches("govtech", "govtech/policy"));
+    }
+}
@@ -0,0 +1,140 @@
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::utils::markdown::html_escape;
+use super::{merge_text_events, MarkdownTransform, RenderContext};
+
+// Renders Obsidian callouts:
+//
+//     > [!warning] Optional title
+//     > Body text
+//
+// `[!type]-` and `[!type]+` make the callout collapsible (collapsed / expanded).
+pub struct Callouts;
+
+struct Callout {
+    kind: String,
+    title: String,
+    foldable: Option<bool>, // Some(open) when collapsible
+}
+
+impl MarkdownTransform for Callouts {
+    fn name(&self) -> &'static str {
+        "callouts"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, _ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let events = merge_text_events(events);
+        let mut result = Vec::with_capacity(events.len());
+        // One entry per open blockquote: the closing HTML if it's a callout
+        let mut open_quotes: Vec<Option<&'static str>> = Vec::new();
+        let mut i = 0;
+
+        while i < events.len() {
+            match &events[i] {
+                Event::Start(Tag::BlockQuote) => {
+                    let marker = match (events.get(i + 1), events.get(i + 2)) {
+                        (Some(Event::Start(Tag::Paragraph)), Some(Event::Text(text))) => parse_marker(text),
+                        _ => None,
+                    };
+
+                    let Some(callout) = marker else {
+                        open_quotes.push(None);
+                        result.push(events[i].clone());
+                        i += 1;
+                        continue;
+                    };
+
+                    result.push(Event::Html(CowStr::from(callout_open_html(&callout))));
+                    open_quotes.push(Some(if callout.foldable.is_some() {
+                        "</div></details>\n"
+                    } else {
+                        "</div></div>\n"
+                    }));
+
+                    // Skip the blockquote start, paragraph start and marker line,
+                    // plus the line break that ends the marker line
+                    i += 3;
+                    while matches!(events.get(i), Some(Event::SoftBreak) | Some(Event::HardBreak)) {
+                        i += 1;
+                    }
+
+                    if matches!(events.get(i), Some(Event::End(Tag::Paragraph))) {
+                        // The first paragraph only held the marker
+                        i += 1;
+                    } else {
+                        result.push(Event::Start(Tag::Paragraph));
+                    }
+                }
+                Event::End(Tag::BlockQuote) => {
+                    match open_quotes.pop().flatten() {
+                        Some(close_html) => result.push(Event::Html(CowStr::from(close_html))),
+                        None => result.push(events[i].clone()),
+                    }
+                    i += 1;
+                }
+                _ => {
+                    result.push(events[i].clone());
+                    i += 1;
+                }
+            }
+        }
+
+        result
+    }
+}
+
+// Parse "[!type]± title" from the first line of a blockquote
+fn parse_marker(text: &str) -> Option<Callout> {
+    let rest = text.strip_prefix("[!")?;
+    let close = rest.find(']')?;
+    let kind = rest[..close].trim().to_lowercase();
+
+    if kind.is_empty() || !kind.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+
+    let mut after = &rest[close + 1..];
+    let foldable = if let Some(stripped) = after.strip_prefix('-') {
+        after = stripped;
+        Some(false)
+    } else if let Some(stripped) = after.strip_prefix('+') {
+        after = stripped;
+        Some(true)
+    } else {
+        None
+    };
+
+    let title = match after.trim() {
+        "" => default_title(&kind),
+        title => title.to_string(),
+    };
+
+    Some(Callout { kind, title, foldable })
+}
+
+// "warning" -> "Warning"
+fn default_title(kind: &str) -> String {
+    let mut chars = kind.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn callout_open_html(callout: &Callout) -> String {
+    let kind = html_escape(&callout.kind);
+    let title = html_escape(&callout.title);
+
+    match callout.foldable {
+        Some(open) => format!(
+            "<details class=\"callout callout-{}\"{}><summary class=\"callout-title\">{}</summary><div class=\"callout-content\">\n",
+            kind,
+            if open { " open" } else { "" },
+            title
+        ),
+        None => format!(
+            "<div class=\"callout callout-{}\"><div class=\"callout-title\">{}</div><div class=\"callout-content\">\n",
+            kind,
+            title
+        ),
+    }
+}
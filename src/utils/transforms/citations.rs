@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::model::BibliographyEntry;
+use crate::utils::citations::{find_citations, get_bibliography, Citation, Reference};
+use crate::utils::markdown::html_escape;
+use crate::utils::site_config::{get_site_config, CitationStyle};
+use super::{merge_text_events, MarkdownTransform, RenderContext};
+
+// Resolves `[@key]` citations against the site bibliography and collects the
+// cited works into `ctx.bibliography`, which the post page lists at the end.
+// Citations with unknown keys are left as written; the loader reports them.
+pub struct Citations;
+
+impl MarkdownTransform for Citations {
+    fn name(&self) -> &'static str {
+        "citations"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let bibliography = get_bibliography();
+        if bibliography.is_empty() {
+            return events;
+        }
+
+        let style = get_site_config().citations.style;
+        let mut cited: Vec<&Reference> = Vec::new();
+        let mut result = Vec::new();
+        let mut skip_depth = 0;
+
+        for event in merge_text_events(events) {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) | Event::Start(Tag::Link(..)) => {
+                    skip_depth += 1;
+                    result.push(event);
+                }
+                Event::End(Tag::CodeBlock(_)) | Event::End(Tag::Link(..)) => {
+                    skip_depth -= 1;
+                    result.push(event);
+                }
+                Event::Text(text) if skip_depth == 0 && text.contains("[@") => {
+                    let mut last_end = 0;
+
+                    for citation in find_citations(&text) {
+                        let Some(references) = citation.items.iter()
+                            .map(|(key, _)| bibliography.get(key))
+                            .collect::<Option<Vec<_>>>() else {
+                            continue;
+                        };
+
+                        for reference in references {
+                            if !cited.iter().any(|c| c.key == reference.key) {
+                                cited.push(reference);
+                            }
+                        }
+
+                        if citation.start > last_end {
+                            result.push(Event::Text(CowStr::from(text[last_end..citation.start].to_string())));
+                        }
+                        result.push(Event::Html(CowStr::from(render_citation(&citation, &cited, bibliography, style))));
+                        last_end = citation.end;
+                    }
+
+                    if last_end < text.len() {
+                        result.push(Event::Text(CowStr::from(text[last_end..].to_string())));
+                    }
+                }
+                _ => result.push(event),
+            }
+        }
+
+        ctx.bibliography = bibliography_entries(cited, style);
+        result
+    }
+}
+
+fn render_citation(
+    citation: &Citation,
+    cited: &[&Reference],
+    bibliography: &HashMap<String, Reference>,
+    style: CitationStyle,
+) -> String {
+    let items: Vec<String> = citation.items.iter()
+        .map(|(key, locator)| {
+            let label = match style {
+                CitationStyle::Numeric => {
+                    let number = cited.iter().position(|c| &c.key == key).unwrap_or(0) + 1;
+                    number.to_string()
+                }
+                CitationStyle::AuthorDate => {
+                    let reference = &bibliography[key];
+                    format!("{} {}", reference.short_authors(), reference.year.as_deref().unwrap_or("n.d."))
+                }
+            };
+
+            let link = format!("<a href=\"#{}\">{}</a>", anchor_id(key), html_escape(&label));
+            match locator {
+                Some(locator) => format!("{}, {}", link, html_escape(locator)),
+                None => link,
+            }
+        })
+        .collect();
+
+    let (open, close) = match style {
+        CitationStyle::Numeric => ("[", "]"),
+        CitationStyle::AuthorDate => ("(", ")"),
+    };
+
+    format!("<span class=\"citation\">{}{}{}</span>", open, items.join("; "), close)
+}
+
+// Numbered styles list works in citation order, author-date styles alphabetically
+fn bibliography_entries(mut cited: Vec<&Reference>, style: CitationStyle) -> Vec<BibliographyEntry> {
+    if style == CitationStyle::AuthorDate {
+        cited.sort_by_key(|r| (r.authors.join("; ").to_lowercase(), r.year.clone()));
+    }
+
+    cited.iter()
+        .enumerate()
+        .map(|(i, reference)| BibliographyEntry {
+            id: anchor_id(&reference.key),
+            label: match style {
+                CitationStyle::Numeric => (i + 1).to_string(),
+                CitationStyle::AuthorDate => String::new(),
+            },
+            html: reference.to_html(),
+        })
+        .collect()
+}
+
+// Citation keys can contain `:` and `/`, which don't belong in a fragment.
+// Other characters are written as `_` and their UTF-8 bytes in hex, so keys
+// that only differ in punctuation (`a:b`, `a/b`) keep separate anchors.
+fn anchor_id(key: &str) -> String {
+    let mut id = String::from("ref-");
+
+    for c in key.chars() {
+        if c.is_alphanumeric() || c == '-' {
+            id.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                id.push_str(&format!("_{:02X}", byte));
+            }
+        }
+    }
+
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchors_are_distinct_for_distinct_keys() {
+        let keys = ["a:b", "a/b", "a_b", "a_3Ab", "a-b", "ab"];
+        let ids: Vec<String> = keys.iter().map(|key| anchor_id(key)).collect();
+
+        assert_eq!(ids[0], "ref-a_3Ab");
+        assert_eq!(ids[1], "ref-a_2Fb");
+        for (i, id) in ids.iter().enumerate() {
+            assert!(!ids[i + 1..].contains(id), "{} is used twice", id);
+        }
+    }
+}
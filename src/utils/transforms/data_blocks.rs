@@ -0,0 +1,113 @@
+use std::fs;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag};
+use crate::utils::charts::{data_block_kind, parse_csv, render_chart, render_table, resolve_data_file, ChartOptions, ChartType};
+use crate::utils::shortcodes::{tokenize, unquote};
+use super::{MarkdownTransform, RenderContext};
+
+// Renders data blocks server-side:
+//
+//     ```csv caption="Quarterly spend"      -> an HTML table
+//     ```chart type=line title="Visitors"   -> a static SVG chart
+//
+// The CSV can be the block body or a file beside the post (`file=data.csv`).
+// Charts also take `x=column` and `y=col1,col2`. Blocks that can't be
+// rendered are reported and left as code.
+pub struct DataBlocks;
+
+impl MarkdownTransform for DataBlocks {
+    fn name(&self) -> &'static str {
+        "data_blocks"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let mut result = Vec::with_capacity(events.len());
+        let mut block: Option<(String, String, Vec<Event<'a>>)> = None;
+        let mut charts = 0;
+
+        for event in events {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) if data_block_kind(info).is_some() => {
+                    let (kind, args) = data_block_kind(info).unwrap_or_default();
+                    block = Some((kind.to_string(), args.to_string(), vec![event]));
+                }
+                Event::End(Tag::CodeBlock(_)) if block.is_some() => {
+                    let (kind, args, mut original) = block.take().unwrap();
+                    original.push(event);
+
+                    let body: String = original.iter()
+                        .filter_map(|e| match e {
+                            Event::Text(text) => Some(text.as_ref()),
+                            _ => None,
+                        })
+                        .collect();
+
+                    if kind == "chart" {
+                        charts += 1;
+                    }
+
+                    match render_block(&kind, &args, &body, charts, ctx) {
+                        // Chart SVG isn't on the sanitiser's allowlist
+                        Ok(html) if kind == "chart" => result.push(Event::Html(CowStr::from(ctx.trusted(html)))),
+                        Ok(html) => result.push(Event::Html(CowStr::from(html))),
+                        Err(e) => {
+                            ctx.report(format!("{} block: {}", kind, e));
+                            result.extend(original);
+                        }
+                    }
+                }
+                event => match block.as_mut() {
+                    Some((_, _, original)) => original.push(event),
+                    None => result.push(event),
+                },
+            }
+        }
+
+        // Unterminated block, leave it as it was
+        if let Some((_, _, original)) = block {
+            result.extend(original);
+        }
+
+        result
+    }
+}
+
+fn render_block(kind: &str, args: &str, body: &str, chart_number: usize, ctx: &RenderContext) -> Result<String, String> {
+    let mut file = None;
+    let mut caption = None;
+    let mut options = ChartOptions { chart_type: ChartType::Line, title: None, x: None, y: Vec::new() };
+
+    for token in tokenize(args) {
+        let (key, value) = token.split_once('=')
+            .ok_or_else(|| format!("expected `key=value`, found `{}`", token))?;
+        let value = unquote(value).to_string();
+
+        match key {
+            "file" => file = Some(value),
+            "caption" => caption = Some(value),
+            "title" if kind == "chart" => options.title = Some(value),
+            "type" if kind == "chart" => options.chart_type = ChartType::parse(&value)?,
+            "x" if kind == "chart" => options.x = Some(value),
+            "y" if kind == "chart" => options.y = value.split(',').map(|c| c.trim().to_string()).collect(),
+            other => return Err(format!("unknown option `{}`", other)),
+        }
+    }
+
+    let csv = match file {
+        Some(file) => {
+            let path = resolve_data_file(ctx.source_path.as_deref(), &file)?;
+            fs::read_to_string(&path).map_err(|e| format!("can't read {}: {}", path.display(), e))?
+        }
+        None => body.to_string(),
+    };
+    let table = parse_csv(&csv)?;
+
+    match kind {
+        "chart" => {
+            if options.title.is_none() {
+                options.title = caption;
+            }
+            render_chart(&table, &options, &format!("chart-{}", chart_number))
+        }
+        _ => Ok(render_table(&table, caption.as_deref())),
+    }
+}
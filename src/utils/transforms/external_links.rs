@@ -0,0 +1,49 @@
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::utils::markdown::html_escape;
+use super::{MarkdownTransform, RenderContext};
+
+// Opens absolute http(s) links in a new tab and marks them for styling
+pub struct ExternalLinks;
+
+impl MarkdownTransform for ExternalLinks {
+    fn name(&self) -> &'static str {
+        "external_links"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, _ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let mut result = Vec::with_capacity(events.len());
+        // Links can't nest, so a flag is enough to match the closing tag
+        let mut in_external_link = false;
+
+        for event in events {
+            match event {
+                Event::Start(Tag::Link(_, ref dest, ref title)) if is_external(dest) => {
+                    let title_attr = if title.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" title=\"{}\"", html_escape(title))
+                    };
+
+                    result.push(Event::Html(CowStr::from(format!(
+                        "<a href=\"{}\"{} class=\"external-link\" target=\"_blank\" rel=\"noopener noreferrer\">",
+                        html_escape(dest),
+                        title_attr
+                    ))));
+                    in_external_link = true;
+                }
+                Event::End(Tag::Link(..)) if in_external_link => {
+                    result.push(Event::Html(CowStr::from("</a>")));
+                    in_external_link = false;
+                }
+                _ => result.push(event),
+            }
+        }
+
+        result
+    }
+}
+
+fn is_external(dest: &str) -> bool {
+    let dest = dest.to_lowercase();
+    dest.starts_with("http://") || dest.starts_with("https://")
+}
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::utils::format::truncate_on_word_boundary;
+use crate::utils::glossary::{get_glossary, Glossary};
+use crate::utils::markdown::{html_escape, markdown_to_plain_text};
+use super::{merge_text_events, MarkdownTransform, RenderContext};
+
+// Longest definition shown in the hover tooltip, in characters
+const TOOLTIP_LENGTH: usize = 200;
+
+// Links the first use of each glossary term in a post to its entry on
+// /glossary, with the definition as a tooltip. Terms in code, headings,
+// links and image text are left alone.
+pub struct GlossaryTerms;
+
+impl MarkdownTransform for GlossaryTerms {
+    fn name(&self) -> &'static str {
+        "glossary"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, _ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        link_glossary_terms(events, get_glossary())
+    }
+}
+
+fn link_glossary_terms<'a>(events: Vec<Event<'a>>, glossary: &Glossary) -> Vec<Event<'a>> {
+    if glossary.is_empty() {
+        return events;
+    }
+
+    let mut result = Vec::new();
+    let mut linked: HashSet<String> = HashSet::new();
+    let mut skip_depth = 0;
+
+    for event in merge_text_events(events) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Start(Tag::Heading(..))
+            | Event::Start(Tag::Link(..)) | Event::Start(Tag::Image(..)) => {
+                skip_depth += 1;
+                result.push(event);
+            }
+            Event::End(Tag::CodeBlock(_)) | Event::End(Tag::Heading(..))
+            | Event::End(Tag::Link(..)) | Event::End(Tag::Image(..)) => {
+                skip_depth -= 1;
+                result.push(event);
+            }
+            Event::Text(text) if skip_depth == 0 => {
+                result.extend(link_terms(&text, glossary, &mut linked));
+            }
+            _ => result.push(event),
+        }
+    }
+
+    result
+}
+
+fn link_terms<'a>(text: &str, glossary: &Glossary, linked: &mut HashSet<String>) -> Vec<Event<'a>> {
+    let mut events = Vec::new();
+    let mut last_end = 0;
+
+    for (start, end, entry) in glossary.find_terms(text) {
+        if !linked.insert(entry.slug.clone()) {
+            continue;
+        }
+
+        if start > last_end {
+            events.push(Event::Text(CowStr::from(text[last_end..start].to_string())));
+        }
+
+        let tooltip = truncate_on_word_boundary(&markdown_to_plain_text(&entry.definition), TOOLTIP_LENGTH);
+
+        events.push(Event::Html(CowStr::from(format!(
+            "<a href=\"/glossary#{}\" class=\"glossary-term\"><abbr title=\"{}\">{}</abbr></a>",
+            html_escape(&entry.slug),
+            html_escape(&tooltip),
+            html_escape(&text[start..end])
+        ))));
+
+        last_end = end;
+    }
+
+    if last_end < text.len() {
+        events.push(Event::Text(CowStr::from(text[last_end..].to_string())));
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use pulldown_cmark::{html, Parser};
+    use crate::model::GlossaryEntry;
+    use crate::utils::jd_content_loader::slug_from_title;
+    use crate::utils::markdown::markdown_options;
+    use super::*;
+
+    fn entry(term: &str, aliases: &[&str], definition: &str) -> GlossaryEntry {
+        GlossaryEntry {
+            term: term.to_string(),
+            slug: slug_from_title(term),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            definition: definition.to_string(),
+        }
+    }
+
+    fn render(markdown: &str) -> String {
+        let glossary = Glossary::new(vec![
+            entry("ATO", &["Authority to Operate"], "Permission to run a *system*."),
+            entry("Authority", &[], "Someone in charge."),
+            entry("Zero trust", &[], "Never trust, always verify."),
+        ]);
+        let events: Vec<Event> = Parser::new_ext(markdown, markdown_options()).collect();
+
+        let mut html = String::new();
+        html::push_html(&mut html, link_glossary_terms(events, &glossary).into_iter());
+        html
+    }
+
+    #[test]
+    fn links_only_the_first_use_of_each_term() {
+        let html = render("An ATO is an Authority to Operate. Another ATO later.\n\nZero trust, then zero trust again.\n");
+
+        assert_eq!(html.matches("href=\"/glossary#ato\"").count(), 1, "{}", html);
+        assert!(html.contains("<a href=\"/glossary#ato\" class=\"glossary-term\"><abbr title=\"Permission to run a system.\">ATO</abbr></a> is an Authority to Operate"), "{}", html);
+        // The longer alias isn't read as the shorter term inside it
+        assert!(!html.contains("/glossary#authority"), "{}", html);
+        assert_eq!(html.matches("/glossary#zero-trust").count(), 1, "{}", html);
+    }
+
+    #[test]
+    fn abbreviations_match_case_exactly_and_terms_in_any_case() {
+        let html = render("Get an ato from the AUTHORITY about ZERO TRUST.\n");
+
+        assert!(!html.contains("/glossary#ato"), "{}", html);
+        assert!(html.contains("<abbr title=\"Someone in charge.\">AUTHORITY</abbr>"), "{}", html);
+        assert!(html.contains("<abbr title=\"Never trust, always verify.\">ZERO TRUST</abbr>"), "{}", html);
+    }
+
+    #[test]
+    fn skips_code_headings_and_links() {
+        let html = render("# ATO\n\n`ATO` and [ATO](/ato) and ![ATO](/a.png)\n\n```\nATO\n```\n\nFinally ATO.\n");
+
+        assert_eq!(html.matches("glossary-term").count(), 1, "{}", html);
+        assert!(html.contains("Finally <a href=\"/glossary#ato\""), "{}", html);
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::model::TocEntry;
+use crate::utils::markdown::html_escape;
+use super::{MarkdownTransform, RenderContext};
+
+// Gives every heading without an explicit id a slug id so sections can be linked to.
+// Duplicate headings get a numeric suffix ("setup", "setup-1", ...).
+// The headings are also recorded as the post's table of contents.
+pub struct HeadingIds;
+
+impl MarkdownTransform for HeadingIds {
+    fn name(&self) -> &'static str {
+        "heading_ids"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let mut result = Vec::with_capacity(events.len());
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut iter = events.into_iter();
+
+        while let Some(event) = iter.next() {
+            let (level, classes) = match event {
+                Event::Start(Tag::Heading(level, None, ref classes)) => (level, classes.join(" ")),
+                _ => {
+                    result.push(event);
+                    continue;
+                }
+            };
+
+            // Gather the heading's inline events so its text can be slugged
+            let mut inner = Vec::new();
+            let mut text = String::new();
+            for event in iter.by_ref() {
+                match &event {
+                    Event::End(Tag::Heading(..)) => break,
+                    Event::Text(t) | Event::Code(t) => text.push_str(t),
+                    _ => {}
+                }
+                inner.push(event);
+            }
+
+            let mut id = heading_slug(&text);
+            if id.is_empty() {
+                id = "section".to_string();
+            }
+            let count = seen.entry(id.clone()).or_insert(0);
+            if *count > 0 {
+                id = format!("{}-{}", id, count);
+            }
+            *count += 1;
+
+            ctx.toc.push(TocEntry {
+                level: level as u8,
+                id: id.clone(),
+                text: text.trim().to_string(),
+            });
+
+            let class_attr = if classes.is_empty() {
+                String::new()
+            } else {
+                format!(" class=\"{}\"", html_escape(&classes))
+            };
+
+            result.push(Event::Html(CowStr::from(format!("<{} id=\"{}\"{}>", level, html_escape(&id), class_attr))));
+            result.extend(inner);
+            result.push(Event::Html(CowStr::from(format!("</{}>\n", level))));
+        }
+
+        result
+    }
+}
+
+// "Getting Started: Setup" -> "getting-started-setup"
+pub fn heading_slug(text: &str) -> String {
+    let mut slug = String::new();
+
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
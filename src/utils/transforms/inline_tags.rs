@@ -0,0 +1,85 @@
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::utils::feeds::encode_path_segment;
+use crate::utils::markdown::html_escape;
+use crate::utils::tags::find_inline_tags;
+use super::{merge_text_events, MarkdownTransform, RenderContext};
+
+// Renders inline `#tags` as links to the tag filter on the home page.
+// Tags in code, headings and link text are left as they are.
+pub struct InlineTags;
+
+impl MarkdownTransform for InlineTags {
+    fn name(&self) -> &'static str {
+        "inline_tags"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, _ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let mut result = Vec::new();
+        let mut skip_depth = 0;
+
+        for event in merge_text_events(events) {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) | Event::Start(Tag::Heading(..)) | Event::Start(Tag::Link(..)) => {
+                    skip_depth += 1;
+                    result.push(event);
+                }
+                Event::End(Tag::CodeBlock(_)) | Event::End(Tag::Heading(..)) | Event::End(Tag::Link(..)) => {
+                    skip_depth -= 1;
+                    result.push(event);
+                }
+                Event::Text(text) if skip_depth == 0 && text.contains('#') => {
+                    result.extend(link_tags(&text));
+                }
+                _ => result.push(event),
+            }
+        }
+
+        result
+    }
+}
+
+fn link_tags<'a>(text: &str) -> Vec<Event<'a>> {
+    let mut events = Vec::new();
+    let mut last_end = 0;
+
+    for (start, end, tag) in find_inline_tags(text) {
+        if start > last_end {
+            events.push(Event::Text(CowStr::from(text[last_end..start].to_string())));
+        }
+
+        events.push(Event::Html(CowStr::from(format!(
+            "<a href=\"/?tag={}\" class=\"inline-tag\">#{}</a>",
+            encode_path_segment(&tag),
+            html_escape(&tag)
+        ))));
+
+        last_end = end;
+    }
+
+    if last_end < text.len() {
+        events.push(Event::Text(CowStr::from(text[last_end..].to_string())));
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_links_are_encoded() {
+        let events = link_tags("About #govtech/policy and #café.");
+        let html: Vec<String> = events.iter()
+            .filter_map(|event| match event {
+                Event::Html(html) => Some(html.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(html, [
+            "<a href=\"/?tag=govtech%2Fpolicy\" class=\"inline-tag\">#govtech/policy</a>",
+            "<a href=\"/?tag=caf%C3%A9\" class=\"inline-tag\">#café</a>",
+        ]);
+    }
+}
@@ -0,0 +1,210 @@
+mod callouts;
+mod citations;
+mod data_blocks;
+mod external_links;
+mod glossary;
+mod heading_ids;
+mod inline_tags;
+mod post_queries;
+mod shortcodes;
+mod sidenotes;
+mod snippets;
+mod syntax_highlight;
+mod wiki_links;
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use pulldown_cmark::{CowStr, Event};
+use leptos::logging::log;
+use crate::model::{BibliographyEntry, Project, TocEntry};
+
+pub use callouts::Callouts;
+pub use citations::Citations;
+pub use data_blocks::DataBlocks;
+pub use external_links::ExternalLinks;
+pub use glossary::GlossaryTerms;
+pub use heading_ids::HeadingIds;
+pub use inline_tags::InlineTags;
+pub use post_queries::PostQueries;
+pub use shortcodes::Shortcodes;
+pub use sidenotes::Sidenotes;
+pub use snippets::Snippets;
+pub use syntax_highlight::SyntaxHighlight;
+pub use wiki_links::WikiLinks;
+
+// What a transform can see about the post being rendered and the rest of the site
+pub struct RenderContext<'p> {
+    pub projects: &'p [Project],
+    // File the markdown came from, for resolving paths relative to the post
+    pub source_path: Option<String>,
+    // Problems found while rendering (bad shortcode arguments, missing posts, ...)
+    pub diagnostics: Vec<String>,
+    // Headings collected by the `heading_ids` transform
+    pub toc: Vec<TocEntry>,
+    // References cited in the post, collected by the `citations` transform
+    pub bibliography: Vec<BibliographyEntry>,
+    // Generated markup the sanitiser would strip, such as chart SVG. It's
+    // kept out of the rendered HTML until after sanitising (see `trusted`).
+    pub trusted_html: Vec<String>,
+    // Random per render, so a post can't write a placeholder of its own
+    pub(crate) trusted_nonce: u64,
+}
+
+impl<'p> RenderContext<'p> {
+    pub fn new(projects: &'p [Project]) -> Self {
+        RenderContext {
+            projects,
+            source_path: None,
+            diagnostics: Vec::new(),
+            toc: Vec::new(),
+            bibliography: Vec::new(),
+            trusted_html: Vec::new(),
+            trusted_nonce: RandomState::new().build_hasher().finish(),
+        }
+    }
+
+    pub fn with_source_path(mut self, path: &str) -> Self {
+        self.source_path = Some(path.to_string());
+        self
+    }
+
+    pub fn report(&mut self, message: impl Into<String>) {
+        self.diagnostics.push(message.into());
+    }
+
+    // Set aside markup a transform generated itself, returning a placeholder
+    // to emit instead. `render_markdown` swaps the markup back in after
+    // sanitising, so posts can't use the same tags in their own HTML.
+    pub fn trusted(&mut self, html: String) -> String {
+        self.trusted_html.push(html);
+        trusted_placeholder(self.trusted_nonce, self.trusted_html.len() - 1)
+    }
+}
+
+// Plain text, which the sanitiser always keeps as it is, whatever its allowlist
+pub(crate) fn trusted_placeholder(nonce: u64, index: usize) -> String {
+    format!("\u{FFFC}trusted-{:016x}-{}\u{FFFC}", nonce, index)
+}
+
+// A rendering extension applied by `markdown_to_html`.
+// `preprocess` sees the raw markdown before parsing, `transform` sees the
+// full pulldown-cmark event stream. Both default to passing input through.
+pub trait MarkdownTransform: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn preprocess(&self, source: String, _ctx: &mut RenderContext) -> String {
+        source
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, _ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        events
+    }
+}
+
+pub type TransformFactory = fn() -> Box<dyn MarkdownTransform>;
+
+// Named factories that the `markdown.transforms` list in site config can refer to
+static REGISTRY: Mutex<Vec<(String, TransformFactory)>> = Mutex::new(Vec::new());
+
+fn builtin_transforms() -> Vec<(&'static str, TransformFactory)> {
+    vec![
+        ("shortcodes", || Box::new(Shortcodes)),
+        ("snippets", || Box::new(Snippets)),
+        ("data_blocks", || Box::new(DataBlocks)),
+        ("syntax_highlight", || Box::new(SyntaxHighlight)),
+        ("post_queries", || Box::new(PostQueries)),
+        ("wiki_links", || Box::new(WikiLinks)),
+        ("callouts", || Box::new(Callouts)),
+        ("citations", || Box::new(Citations)),
+        ("inline_tags", || Box::new(InlineTags)),
+        ("glossary", || Box::new(GlossaryTerms)),
+        ("heading_ids", || Box::new(HeadingIds)),
+        ("external_links", || Box::new(ExternalLinks)),
+        ("sidenotes", || Box::new(Sidenotes)),
+    ]
+}
+
+// Register a custom transform so it can be enabled by name from site config.
+// Registering an existing name replaces it, including the built-ins.
+pub fn register_transform(name: &str, factory: TransformFactory) {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.retain(|(existing, _)| existing != name);
+    registry.push((name.to_string(), factory));
+}
+
+fn find_factory(name: &str) -> Option<TransformFactory> {
+    let registry = REGISTRY.lock().unwrap();
+
+    registry.iter()
+        .find(|(registered, _)| registered == name)
+        .map(|(_, factory)| *factory)
+        .or_else(|| {
+            builtin_transforms().into_iter()
+                .find(|(builtin, _)| *builtin == name)
+                .map(|(_, factory)| factory)
+        })
+}
+
+// The ordered list of transforms to run for a render
+pub struct TransformPipeline {
+    transforms: Vec<Box<dyn MarkdownTransform>>,
+}
+
+impl TransformPipeline {
+    pub fn new() -> Self {
+        TransformPipeline { transforms: Vec::new() }
+    }
+
+    // Build the pipeline from transform names, in order. Unknown names are skipped.
+    pub fn from_names(names: &[String]) -> Self {
+        let mut pipeline = TransformPipeline::new();
+
+        for name in names {
+            match find_factory(name) {
+                Some(factory) => pipeline.push(factory()),
+                None => log!("Unknown markdown transform in site config: {}", name),
+            }
+        }
+
+        pipeline
+    }
+
+    pub fn push(&mut self, transform: Box<dyn MarkdownTransform>) {
+        self.transforms.push(transform);
+    }
+
+    pub fn preprocess(&self, source: &str, ctx: &mut RenderContext) -> String {
+        self.transforms.iter()
+            .fold(source.to_string(), |source, t| t.preprocess(source, ctx))
+    }
+
+    pub fn transform<'a>(&self, events: Vec<Event<'a>>, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        self.transforms.iter()
+            .fold(events, |events, t| t.transform(events, ctx))
+    }
+}
+
+impl Default for TransformPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// pulldown-cmark splits text at brackets and other inline markers, so
+// transforms that look for patterns inside text merge adjacent runs first
+pub fn merge_text_events(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut merged: Vec<Event> = Vec::with_capacity(events.len());
+
+    for event in events {
+        match (merged.last_mut(), event) {
+            (Some(Event::Text(previous)), Event::Text(text)) => {
+                let combined = format!("{}{}", previous, text);
+                *previous = CowStr::from(combined);
+            }
+            (_, event) => merged.push(event),
+        }
+    }
+
+    merged
+}
@@ -0,0 +1,119 @@
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag};
+use crate::model::Project;
+use crate::utils::format::format_date;
+use crate::utils::markdown::html_escape;
+use crate::utils::query::{PostQuery, QueryFormat};
+use super::{MarkdownTransform, RenderContext};
+
+// Replaces ```posts code blocks with a list of matching posts, e.g.
+//
+//     ```posts tag:rust category:11 sort:date limit:5
+//     ```
+//
+// The query can go on the fence line, in the block body, or both.
+// Invalid queries are reported and the block is left as code.
+pub struct PostQueries;
+
+impl MarkdownTransform for PostQueries {
+    fn name(&self) -> &'static str {
+        "post_queries"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let mut result = Vec::with_capacity(events.len());
+        let mut block: Option<(String, Vec<Event<'a>>)> = None;
+
+        for event in events {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) if query_args(info).is_some() => {
+                    let args = query_args(info).unwrap_or_default().to_string();
+                    block = Some((args, vec![event]));
+                }
+                Event::End(Tag::CodeBlock(_)) if block.is_some() => {
+                    let (mut source, mut original) = block.take().unwrap();
+                    original.push(event);
+
+                    for text in &original {
+                        if let Event::Text(text) = text {
+                            source.push(' ');
+                            source.push_str(text);
+                        }
+                    }
+
+                    match PostQuery::parse(&source) {
+                        Ok(query) => {
+                            let html = render_results(&query, query.run(ctx.projects));
+                            result.push(Event::Html(CowStr::from(html)));
+                        }
+                        Err(e) => {
+                            ctx.report(format!("posts query `{}`: {}", source.trim(), e));
+                            result.extend(original);
+                        }
+                    }
+                }
+                event => match block.as_mut() {
+                    Some((_, original)) => original.push(event),
+                    None => result.push(event),
+                },
+            }
+        }
+
+        // Unterminated block, leave it as it was
+        if let Some((_, original)) = block {
+            result.extend(original);
+        }
+
+        result
+    }
+}
+
+// The query arguments on a ```posts fence line, if it is one
+fn query_args(info: &str) -> Option<&str> {
+    let info = info.trim();
+    let rest = info.strip_prefix("posts")?;
+
+    if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+fn render_results(query: &PostQuery, posts: Vec<&Project>) -> String {
+    if posts.is_empty() {
+        return "<p class=\"post-query-empty\">No matching posts.</p>".to_string();
+    }
+
+    match query.format {
+        QueryFormat::List => {
+            let items: String = posts.iter()
+                .map(|p| format!(
+                    "<li><a href=\"/projects/{}\">{}</a> <span class=\"post-query-meta\">{} · {}</span></li>",
+                    html_escape(&p.slug),
+                    html_escape(&p.title),
+                    html_escape(&p.jd_identifier),
+                    format_date(p.created_at)
+                ))
+                .collect();
+
+            format!("<ul class=\"post-query\">{}</ul>", items)
+        }
+        QueryFormat::Table => {
+            let rows: String = posts.iter()
+                .map(|p| format!(
+                    "<tr><td>{}</td><td><a href=\"/projects/{}\">{}</a></td><td>{}</td><td>{}</td></tr>",
+                    html_escape(&p.jd_identifier),
+                    html_escape(&p.slug),
+                    html_escape(&p.title),
+                    html_escape(p.jd_category.as_ref().map(|c| c.name.as_str()).unwrap_or("")),
+                    format_date(p.created_at)
+                ))
+                .collect();
+
+            format!(
+                "<table class=\"post-query\"><thead><tr><th>ID</th><th>Title</th><th>Category</th><th>Date</th></tr></thead><tbody>{}</tbody></table>",
+                rows
+            )
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::utils::shortcodes::{find_shortcode, join_shortcode_text, render_shortcode, Shortcode};
+use super::{MarkdownTransform, RenderContext};
+
+// Replaces `{{< name args >}}` shortcodes with the output of their handler
+// (see `utils::shortcodes`). A shortcode on its own line replaces the whole
+// paragraph so block embeds aren't wrapped in `<p>`.
+pub struct Shortcodes;
+
+impl MarkdownTransform for Shortcodes {
+    fn name(&self) -> &'static str {
+        "shortcodes"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let events = join_shortcode_text(events);
+        let mut result = Vec::with_capacity(events.len());
+        let mut in_code_block = false;
+        let mut i = 0;
+
+        while i < events.len() {
+            match &events[i] {
+                Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+                Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+                Event::Start(Tag::Paragraph) if !in_code_block => {
+                    // Paragraph holding nothing but a shortcode
+                    if let (Some(Event::Text(text)), Some(Event::End(Tag::Paragraph))) = (events.get(i + 1), events.get(i + 2)) {
+                        let trimmed = text.trim();
+                        if let Some((0, end, shortcode)) = find_shortcode(trimmed) {
+                            if end == trimmed.len() {
+                                match render(&shortcode, trimmed, ctx) {
+                                    Some(html) => result.push(Event::Html(CowStr::from(html))),
+                                    // Keep the paragraph as it was so the shortcode stays visible
+                                    None => result.extend(events[i..i + 3].iter().cloned()),
+                                }
+                                i += 3;
+                                continue;
+                            }
+                        }
+                    }
+                }
+                Event::Text(text) if !in_code_block && text.contains("{{<") => {
+                    result.extend(replace_inline(text, ctx));
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            result.push(events[i].clone());
+            i += 1;
+        }
+
+        result
+    }
+}
+
+fn replace_inline<'a>(text: &str, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+    let mut events = Vec::new();
+    let mut rest = text;
+
+    while let Some((start, end, shortcode)) = find_shortcode(rest) {
+        let source = &rest[start..end];
+
+        match render(&shortcode, source, ctx) {
+            Some(html) => {
+                if start > 0 {
+                    events.push(Event::Text(CowStr::from(rest[..start].to_string())));
+                }
+                events.push(Event::Html(CowStr::from(html)));
+            }
+            // Leave shortcodes that failed to render as text so they're visible
+            None => events.push(Event::Text(CowStr::from(rest[..end].to_string()))),
+        }
+
+        rest = &rest[end..];
+    }
+
+    if !rest.is_empty() {
+        events.push(Event::Text(CowStr::from(rest.to_string())));
+    }
+
+    events
+}
+
+fn render(shortcode: &Shortcode, source: &str, ctx: &mut RenderContext) -> Option<String> {
+    match render_shortcode(shortcode, ctx) {
+        Ok(html) => Some(html),
+        Err(e) => {
+            ctx.report(format!("{}: {}", source, e));
+            None
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::utils::markdown::html_escape;
+use super::{MarkdownTransform, RenderContext};
+
+// Renders footnotes as sidenotes next to their reference instead of at the
+// bottom of the post. The markup works without JavaScript: on wide screens the
+// note sits in the margin, on narrow screens the checkbox toggles it inline
+// (and pointer devices also get a hover preview). Each note links back to its
+// reference for screen reader and keyboard users.
+pub struct Sidenotes;
+
+impl MarkdownTransform for Sidenotes {
+    fn name(&self) -> &'static str {
+        "sidenotes"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, _ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let (events, mut definitions) = take_definitions(events);
+
+        if definitions.is_empty() {
+            return events;
+        }
+
+        let mut result = Vec::with_capacity(events.len());
+        // Sidenotes and footnotes share one sequence of numbers
+        let mut numbers: HashMap<String, (usize, bool)> = HashMap::new();
+        let mut footnotes = Vec::new();
+
+        for event in events {
+            let label = match &event {
+                Event::FootnoteReference(label) => label.to_string(),
+                _ => {
+                    result.push(event);
+                    continue;
+                }
+            };
+
+            // Later references to an already placed note just point at it
+            if let Some((number, sidenote)) = numbers.get(&label) {
+                let target = if *sidenote { format!("sn-{}", number) } else { html_escape(&label) };
+                result.push(Event::Html(CowStr::from(format!(
+                    "<sup class=\"sidenote-ref\"><a href=\"#{}\">{}</a></sup>",
+                    target, number
+                ))));
+                continue;
+            }
+
+            let Some(definition) = definitions.remove(&label) else {
+                // No definition, leave it to the default renderer
+                result.push(event);
+                continue;
+            };
+
+            let number = numbers.len() + 1;
+
+            // Lists, quotes and code can't sit inside a paragraph, so notes
+            // with them stay footnotes at the end of the post
+            if !is_inline(&definition) {
+                result.push(Event::Html(CowStr::from(format!(
+                    "<sup class=\"footnote-reference\"><a href=\"#{}\">{}</a></sup>",
+                    html_escape(&label), number
+                ))));
+                numbers.insert(label.clone(), (number, false));
+                footnotes.push((label, number, definition));
+                continue;
+            }
+
+            numbers.insert(label, (number, true));
+
+            result.push(Event::Html(CowStr::from(format!(
+                "<span class=\"sidenote-wrapper\">\
+<label for=\"sn-toggle-{0}\" class=\"sidenote-ref\" id=\"snref-{0}\">{0}</label>\
+<input type=\"checkbox\" id=\"sn-toggle-{0}\" class=\"sidenote-checkbox\" aria-label=\"Show note {0}\" />\
+<span class=\"sidenote\" id=\"sn-{0}\" role=\"note\"><span class=\"sidenote-number\">{0}</span> ",
+                number
+            ))));
+            result.extend(inline_definition(definition));
+            result.push(Event::Html(CowStr::from(format!(
+                " <a href=\"#snref-{}\" class=\"sidenote-backref\" aria-label=\"Back to reference\">↩</a></span></span>",
+                number
+            ))));
+        }
+
+        for (label, number, definition) in footnotes {
+            result.push(Event::Html(CowStr::from(format!(
+                "<div class=\"footnote-definition\" id=\"{}\"><sup class=\"footnote-definition-label\">{}</sup>\n",
+                html_escape(&label), number
+            ))));
+            result.extend(definition);
+            result.push(Event::Html(CowStr::from("</div>\n")));
+        }
+
+        // Definitions that were never referenced keep the default rendering
+        let mut unreferenced: Vec<_> = definitions.into_iter().collect();
+        unreferenced.sort_by(|a, b| a.0.cmp(&b.0));
+        for (label, definition) in unreferenced {
+            result.push(Event::Start(Tag::FootnoteDefinition(CowStr::from(label.clone()))));
+            result.extend(definition);
+            result.push(Event::End(Tag::FootnoteDefinition(CowStr::from(label))));
+        }
+
+        result
+    }
+}
+
+// Pull footnote definitions out of the event stream, keyed by label. A
+// definition ending in an indented block can swallow the next one, so
+// they're collected on a stack.
+fn take_definitions(events: Vec<Event<'_>>) -> (Vec<Event<'_>>, HashMap<String, Vec<Event<'_>>>) {
+    let mut remaining = Vec::with_capacity(events.len());
+    let mut definitions = HashMap::new();
+    let mut open: Vec<(String, Vec<Event>)> = Vec::new();
+
+    for event in events {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                open.push((label.to_string(), Vec::new()));
+            }
+            Event::End(Tag::FootnoteDefinition(_)) => {
+                if let Some((label, body)) = open.pop() {
+                    definitions.insert(label, body);
+                }
+            }
+            event => match open.last_mut() {
+                Some((_, body)) => body.push(event),
+                None => remaining.push(event),
+            },
+        }
+    }
+
+    (remaining, definitions)
+}
+
+// Whether a definition is only paragraphs of inline text, so it can become
+// a sidenote
+fn is_inline(events: &[Event<'_>]) -> bool {
+    events.iter().all(|event| match event {
+        Event::Start(tag) => !matches!(tag,
+            Tag::Heading(..) | Tag::BlockQuote | Tag::CodeBlock(_) | Tag::List(_) | Tag::Item
+                | Tag::FootnoteDefinition(_) | Tag::Table(_) | Tag::TableHead | Tag::TableRow
+                | Tag::TableCell),
+        Event::Rule => false,
+        _ => true,
+    })
+}
+
+// Sidenotes live inside a paragraph, so the definition's paragraphs
+// become line breaks instead of nested `<p>` elements
+fn inline_definition(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut result = Vec::with_capacity(events.len());
+    let mut paragraphs = 0;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Paragraph) => {
+                if paragraphs > 0 {
+                    result.push(Event::Html(CowStr::from("<br />")));
+                }
+                paragraphs += 1;
+            }
+            Event::End(Tag::Paragraph) => {}
+            event => result.push(event),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use pulldown_cmark::{html, Options, Parser};
+    use super::*;
+
+    fn render(markdown: &str) -> String {
+        let events: Vec<Event> = Parser::new_ext(markdown, Options::ENABLE_FOOTNOTES).collect();
+        let events = Sidenotes.transform(events, &mut RenderContext::new(&[]));
+        let mut html = String::new();
+        html::push_html(&mut html, events.into_iter());
+        html
+    }
+
+    #[test]
+    fn inline_notes_become_sidenotes() {
+        let html = render("Text[^a] and again[^a].\n\n[^a]: A *short* note.\n");
+
+        assert!(html.contains("<span class=\"sidenote-number\">1</span> A <em>short</em> note."), "{}", html);
+        assert!(html.contains("<a href=\"#sn-1\">1</a>"), "{}", html);
+        assert!(!html.contains("footnote-definition"), "{}", html);
+    }
+
+    #[test]
+    fn block_content_stays_a_footnote() {
+        let html = render("A quote[^quote], code[^code] and a note[^note].\n\n\
+            [^quote]: > Quoted.\n\n[^code]:\n    let x = 1;\n\n[^note]: Short.\n");
+        let paragraph = &html[..html.find("</p>").unwrap()];
+
+        // Quotes and code are rendered normally at the end, outside the paragraph
+        assert!(!paragraph.contains("<blockquote>") && !paragraph.contains("<pre>"), "{}", html);
+        assert!(paragraph.contains("<sup class=\"footnote-reference\"><a href=\"#quote\">1</a></sup>"), "{}", html);
+        assert!(paragraph.contains("<sup class=\"footnote-reference\"><a href=\"#code\">2</a></sup>"), "{}", html);
+        assert!(html.contains("<div class=\"footnote-definition\" id=\"quote\"><sup class=\"footnote-definition-label\">1</sup>\n<blockquote>"), "{}", html);
+        assert!(html.contains("<div class=\"footnote-definition\" id=\"code\"><sup class=\"footnote-definition-label\">2</sup>\n<pre>"), "{}", html);
+
+        // Other notes still become sidenotes, numbered after them
+        assert!(paragraph.contains("<span class=\"sidenote-number\">3</span> Short."), "{}", html);
+    }
+}
@@ -0,0 +1,110 @@
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag};
+use crate::utils::site_config::get_site_config;
+use crate::utils::snippets::SnippetInclude;
+use super::{MarkdownTransform, RenderContext};
+
+// Fills ```lang file=path lines=10-42 code blocks from the snippets directory
+// (see `utils::snippets`), so tutorial code stays in sync with files that
+// actually compile. Missing files, bad line ranges and malformed includes
+// are reported as load errors, and the block keeps its own body.
+pub struct Snippets;
+
+impl MarkdownTransform for Snippets {
+    fn name(&self) -> &'static str {
+        "snippets"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        include_snippets(events, ctx, &get_site_config().markdown.snippets_dir)
+    }
+}
+
+fn include_snippets<'a>(events: Vec<Event<'a>>, ctx: &mut RenderContext, snippets_dir: &str) -> Vec<Event<'a>> {
+    let mut result = Vec::with_capacity(events.len());
+    let mut replacing = false;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => {
+                let Some(include) = SnippetInclude::parse(info) else {
+                    result.push(event);
+                    continue;
+                };
+
+                match include.and_then(|include| Ok((include.read_from(snippets_dir)?, include.language))) {
+                    Ok((code, language)) => {
+                        let language = CowStr::from(language);
+                        result.push(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(language.clone()))));
+                        result.push(Event::Text(CowStr::from(code)));
+                        result.push(Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(language))));
+                        replacing = true;
+                    }
+                    Err(e) => {
+                        ctx.report(format!("snippet `{}`: {}", info.trim(), e));
+                        result.push(event);
+                    }
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) if replacing => replacing = false,
+            _ if replacing => {}
+            _ => result.push(event),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use pulldown_cmark::{html, Parser};
+    use super::*;
+
+    // A snippets directory of its own for each test
+    fn snippets_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("snippets-{}-{}", name, std::process::id()));
+        fs::create_dir_all(dir.join("examples")).unwrap();
+        fs::write(dir.join("examples/server.rs"), "fn main() {\n    let a = 1;\n    let b = 2;\n}\n").unwrap();
+        dir
+    }
+
+    fn render(markdown: &str, dir: &Path) -> (String, Vec<String>) {
+        let mut ctx = RenderContext::new(&[]);
+        let events: Vec<Event> = Parser::new(markdown).collect();
+        let events = include_snippets(events, &mut ctx, dir.to_str().unwrap());
+
+        let mut html = String::new();
+        html::push_html(&mut html, events.into_iter());
+        (html, ctx.diagnostics)
+    }
+
+    #[test]
+    fn includes_a_line_range() {
+        let dir = snippets_dir("range");
+        let (html, diagnostics) = render("```rust file=examples/server.rs lines=2-3\n```\n", &dir);
+
+        assert_eq!(html, "<pre><code class=\"language-rust\">let a = 1;\nlet b = 2;\n</code></pre>\n");
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_files_and_ranges_are_reported() {
+        let dir = snippets_dir("missing");
+        let (html, diagnostics) = render(
+            "```rust file=examples/missing.rs\nold\n```\n\n```rust file=examples/server.rs lines=3-9\n```\n\n\
+            ```rust file=../secret.rs\n```\n\n```rust file=examples/server.rs lines=x\n```\n",
+            &dir,
+        );
+
+        assert_eq!(diagnostics.len(), 4, "{:?}", diagnostics);
+        assert!(diagnostics[0].starts_with("snippet `rust file=examples/missing.rs`: can't read snippet"), "{:?}", diagnostics);
+        assert!(diagnostics[1].contains("lines 3-9 are out of range"), "{:?}", diagnostics);
+        assert!(diagnostics[2].contains("must be relative"), "{:?}", diagnostics);
+        assert!(diagnostics[3].ends_with("invalid line range `x`"), "{:?}", diagnostics);
+        // The block keeps what the post wrote
+        assert!(html.contains(">old\n</code>"), "{}", html);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag};
+use crate::utils::highlight::highlight_code;
+use crate::utils::markdown::html_escape;
+use super::{MarkdownTransform, RenderContext};
+
+// Colours fenced code blocks in the languages `utils::highlight` knows,
+// keeping the `<pre><code class="language-...">` markup pulldown-cmark
+// would give them. Other blocks are left as plain code.
+pub struct SyntaxHighlight;
+
+impl MarkdownTransform for SyntaxHighlight {
+    fn name(&self) -> &'static str {
+        "syntax_highlight"
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, _ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let mut result = Vec::with_capacity(events.len());
+        let mut block: Option<(String, Vec<Event<'a>>)> = None;
+
+        for event in events {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => {
+                    let language = info.split_whitespace().next().unwrap_or("").to_string();
+                    block = Some((language, vec![event]));
+                }
+                Event::End(Tag::CodeBlock(_)) if block.is_some() => {
+                    let (language, mut original) = block.take().unwrap();
+                    let code: String = original.iter()
+                        .filter_map(|e| match e {
+                            Event::Text(text) => Some(text.as_ref()),
+                            _ => None,
+                        })
+                        .collect();
+
+                    match highlight_code(&code, &language) {
+                        Some(html) => result.push(Event::Html(CowStr::from(format!(
+                            "<pre><code class=\"language-{}\">{}</code></pre>\n",
+                            html_escape(&language),
+                            html,
+                        )))),
+                        None => {
+                            original.push(event);
+                            result.extend(original);
+                        }
+                    }
+                }
+                event => match block.as_mut() {
+                    Some((_, original)) => original.push(event),
+                    None => result.push(event),
+                },
+            }
+        }
+
+        if let Some((_, original)) = block {
+            result.extend(original);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pulldown_cmark::{html, Parser};
+    use super::*;
+
+    fn render(markdown: &str) -> String {
+        let mut ctx = RenderContext::new(&[]);
+        let events = SyntaxHighlight.transform(Parser::new(markdown).collect(), &mut ctx);
+
+        let mut html = String::new();
+        html::push_html(&mut html, events.into_iter());
+        html
+    }
+
+    #[test]
+    fn highlights_known_languages_only() {
+        assert_eq!(
+            render("```rust ignore\nlet x = \"<a>\";\n```\n"),
+            "<pre><code class=\"language-rust\"><span class=\"hl-keyword\">let</span> x = <span class=\"hl-string\">&quot;&lt;a&gt;&quot;</span>;\n</code></pre>\n",
+        );
+        assert_eq!(render("```text\nlet x\n```\n"), "<pre><code class=\"language-text\">let x\n</code></pre>\n");
+        assert_eq!(render("    let x\n"), "<pre><code>let x\n</code></pre>\n");
+    }
+}
@@ -0,0 +1,186 @@
+use pulldown_cmark::{CowStr, Event, Tag};
+use crate::utils::jd_content_loader::slug_from_title;
+use crate::utils::markdown::{escape_table_wiki_links, html_escape};
+use super::heading_ids::heading_slug;
+use super::{merge_text_events, MarkdownTransform, RenderContext};
+
+// Turns Obsidian `[[Note]]` and `[[Note|display text]]` links into links to the post.
+// A leading Johnny Decimal ID ("12.01 Note") and `.md` extension are ignored when
+// building the slug, and `[[Note#Heading]]` links to the heading anchor.
+// Links to posts that don't exist are reported and shown as plain text.
+pub struct WikiLinks;
+
+impl MarkdownTransform for WikiLinks {
+    fn name(&self) -> &'static str {
+        "wiki_links"
+    }
+
+    fn preprocess(&self, source: String, _ctx: &mut RenderContext) -> String {
+        escape_table_wiki_links(&source)
+    }
+
+    fn transform<'a>(&self, events: Vec<Event<'a>>, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+        let mut result = Vec::new();
+        let mut in_code_block = false;
+
+        for event in merge_text_events(events) {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) => {
+                    in_code_block = true;
+                    result.push(event);
+                }
+                Event::End(Tag::CodeBlock(_)) => {
+                    in_code_block = false;
+                    result.push(event);
+                }
+                Event::Text(text) if !in_code_block && text.contains("[[") => {
+                    result.extend(split_wiki_links(&text, ctx));
+                }
+                _ => result.push(event),
+            }
+        }
+
+        result
+    }
+}
+
+fn split_wiki_links<'a>(text: &str, ctx: &mut RenderContext) -> Vec<Event<'a>> {
+    let mut events = Vec::new();
+    let mut rest = text;
+
+    while let Some(start_pos) = rest.find("[[") {
+        let link_start = start_pos + 2;
+
+        let Some(end_pos) = rest[link_start..].find("]]") else {
+            break;
+        };
+
+        if start_pos > 0 {
+            events.push(Event::Text(CowStr::from(rest[..start_pos].to_string())));
+        }
+
+        let link_content = &rest[link_start..link_start + end_pos];
+        events.push(Event::Html(CowStr::from(wiki_link_html(link_content, ctx))));
+
+        rest = &rest[link_start + end_pos + 2..];
+    }
+
+    if !rest.is_empty() {
+        events.push(Event::Text(CowStr::from(rest.to_string())));
+    }
+
+    events
+}
+
+fn wiki_link_html(link_content: &str, ctx: &mut RenderContext) -> String {
+    // Check for pipe character for display text
+    let (target, display) = match link_content.find('|') {
+        Some(pipe_pos) => (&link_content[..pipe_pos], &link_content[pipe_pos + 1..]),
+        None => (link_content, link_content),
+    };
+
+    let (note, heading) = match target.find('#') {
+        Some(hash_pos) => (&target[..hash_pos], Some(&target[hash_pos + 1..])),
+        None => (target, None),
+    };
+
+    // `[[#Heading]]` links within the current post
+    let mut href = if note.is_empty() {
+        String::new()
+    } else {
+        let slug = slug_from_title(strip_jd_prefix(note.trim_end_matches(".md")));
+
+        // Without the other posts (e.g. a standalone render) every link is trusted
+        if !ctx.projects.is_empty() && !ctx.projects.iter().any(|project| project.slug == slug) {
+            ctx.report(format!("wiki link `[[{}]]` doesn't match any post", link_content));
+            return format!("<span class=\"wiki-link-missing\">{}</span>", html_escape(display));
+        }
+
+        format!("/projects/{}", slug)
+    };
+    if let Some(heading) = heading {
+        href.push('#');
+        href.push_str(&heading_slug(heading));
+    }
+
+    format!("<a href=\"{}\" class=\"wiki-link\">{}</a>", html_escape(&href), html_escape(display))
+}
+
+// "12.01 Building Web Apps" -> "Building Web Apps"
+fn strip_jd_prefix(note: &str) -> &str {
+    match note.split_once(' ') {
+        Some((prefix, rest)) if prefix.chars().all(|c| c.is_ascii_digit() || c == '.') => rest,
+        _ => note,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+    use pulldown_cmark::{html, Parser};
+    use crate::model::Project;
+    use crate::utils::markdown::markdown_options;
+    use super::*;
+
+    fn project(title: &str) -> Project {
+        Project {
+            id: None,
+            title: title.to_string(),
+            slug: slug_from_title(title),
+            summary: String::new(),
+            content: String::new(),
+            tech_stack: Vec::new(),
+            repo_url: None,
+            live_url: None,
+            thumbnail: None,
+            created_at: SystemTime::UNIX_EPOCH,
+            updated_at: SystemTime::UNIX_EPOCH,
+            jd_category: None,
+            original_path: String::new(),
+            jd_identifier: String::new(),
+            related_articles: Vec::new(),
+            allow_html: false,
+            rendered: None,
+        }
+    }
+
+    fn render(markdown: &str) -> (String, Vec<String>) {
+        let projects = vec![project("Other Post")];
+        let mut ctx = RenderContext::new(&projects);
+        let source = WikiLinks.preprocess(markdown.to_string(), &mut ctx);
+        let events: Vec<Event> = Parser::new_ext(&source, markdown_options()).collect();
+        let events = WikiLinks.transform(events, &mut ctx);
+
+        let mut html = String::new();
+        html::push_html(&mut html, events.into_iter());
+        (html, ctx.diagnostics)
+    }
+
+    #[test]
+    fn links_with_aliases_and_headings() {
+        let (html, diagnostics) = render("See [[12.01 Other Post.md|the other one]] and [[Other Post#Some Heading]], or [[#Intro]].\n");
+
+        assert!(html.contains("<a href=\"/projects/other-post\" class=\"wiki-link\">the other one</a>"), "{}", html);
+        assert!(html.contains("<a href=\"/projects/other-post#some-heading\" class=\"wiki-link\">Other Post#Some Heading</a>"), "{}", html);
+        assert!(html.contains("<a href=\"#intro\" class=\"wiki-link\">#Intro</a>"), "{}", html);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn aliases_in_tables_stay_in_their_cell() {
+        let (html, _) = render("| Post | Notes |\n| --- | --- |\n| [[Other Post|alias]] | ok |\n\n```\n| [[Other Post|x]] |\n```\n");
+
+        assert!(html.contains("<td><a href=\"/projects/other-post\" class=\"wiki-link\">alias</a></td>"), "{}", html);
+        assert_eq!(html.matches("<td>").count(), 2, "{}", html);
+        // Code blocks are left as written
+        assert!(html.contains("<code>| [[Other Post|x]] |\n</code>"), "{}", html);
+    }
+
+    #[test]
+    fn unresolved_targets_are_reported() {
+        let (html, diagnostics) = render("A [[Missing Post|missing]] link.\n");
+
+        assert!(html.contains("<span class=\"wiki-link-missing\">missing</span>"), "{}", html);
+        assert_eq!(diagnostics, vec!["wiki link `[[Missing Post|missing]]` doesn't match any post".to_string()]);
+    }
+}